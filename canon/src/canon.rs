@@ -15,6 +15,12 @@ pub enum CanonError {
     InvalidEncoding,
     /// The instance could not be found in storage
     NotFound,
+    /// The bytes fetched from storage do not match the requested hash
+    HashMismatch,
+    /// The byte sequence ended before the value was fully decoded
+    UnexpectedEof,
+    /// The storage failed reading or writing the value
+    Io,
 }
 
 impl Canon for CanonError {
@@ -22,6 +28,9 @@ impl Canon for CanonError {
         let byte = match self {
             CanonError::InvalidEncoding => 0,
             CanonError::NotFound => 1,
+            CanonError::HashMismatch => 2,
+            CanonError::UnexpectedEof => 3,
+            CanonError::Io => 4,
         };
         sink.copy_bytes(&[byte])
     }
@@ -30,6 +39,9 @@ impl Canon for CanonError {
         match u8::decode(source)? {
             0 => Ok(CanonError::InvalidEncoding),
             1 => Ok(CanonError::NotFound),
+            2 => Ok(CanonError::HashMismatch),
+            3 => Ok(CanonError::UnexpectedEof),
+            4 => Ok(CanonError::Io),
             _ => Err(CanonError::InvalidEncoding),
         }
     }
@@ -50,17 +62,84 @@ where
     T: Canon,
 {
     fn encode_to_vec(&self) -> Vec<u8> {
-        let len = self.encoded_len();
-        let mut vec = Vec::with_capacity(len);
-        vec.resize_with(len, || 0);
-        let mut sink = Sink::new(&mut vec);
-        self.encode(&mut sink);
+        // one pass, without computing the length first
+        let mut vec = Vec::new();
+        self.encode(&mut Sink::growable(&mut vec));
         vec
     }
 }
 
+/// Helper trait to stream the encoding of Canon types to a writer
+///
+/// Bytes are written through a bounded buffer, and are the same as the ones
+/// of `EncodeToVec::encode_to_vec`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait EncodeToWriter {
+    /// Encode `Self` into a writer
+    fn encode_to_writer<W: std::io::Write>(
+        &self,
+        writer: W,
+    ) -> std::io::Result<()>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> EncodeToWriter for T
+where
+    T: Canon,
+{
+    fn encode_to_writer<W: std::io::Write>(
+        &self,
+        mut writer: W,
+    ) -> std::io::Result<()> {
+        let mut sink = Sink::writer(&mut writer);
+        self.encode(&mut sink);
+        sink.finish()
+    }
+}
+
+/// Helper trait to decode Canon types streamed from a reader
+///
+/// Only the bytes of the value are read, a field at a time, so unbuffered
+/// readers are best wrapped in a `BufReader`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait DecodeFromReader: Sized {
+    /// Decode `Self` from a reader
+    ///
+    /// Invalid encodings are reported as `io::ErrorKind::InvalidData`.
+    fn decode_from_reader<R: std::io::Read>(reader: R)
+        -> std::io::Result<Self>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<T> DecodeFromReader for T
+where
+    T: Canon,
+{
+    fn decode_from_reader<R: std::io::Read>(
+        mut reader: R,
+    ) -> std::io::Result<Self> {
+        let mut source = Source::reader(&mut reader);
+        let result = T::decode(&mut source);
+        // a failed read explains any decoding error after it
+        source.finish()?;
+        result.map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid encoding: {:?}", err),
+            )
+        })
+    }
+}
+
 /// Trait to read/write values as bytes
 pub trait Canon: Sized + Clone {
+    /// Domain tag prefixed to the bytes behind the `Id` of a value of this
+    /// type
+    ///
+    /// Types with different tags never share an `Id`, even if their
+    /// encodings are equal. Defaults to no tag.
+    const TAG: Option<&'static str> = None;
+
     /// Write the value as bytes to a `Sink`
     fn encode(&self, sink: &mut Sink);
     /// Read the value from bytes in a `Source`
@@ -0,0 +1,306 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::canon::{Canon, CanonError};
+use crate::id::{Id, PAYLOAD_BYTES};
+use crate::store::{walk, Sink, Source, Store};
+
+/// A node that differs between two trees
+///
+/// Nodes are located by their path from the root, each element selecting a
+/// child by its position among the `Id`s its parent decodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A node only present in the new tree, with its encoding
+    Added {
+        /// The location of the node
+        path: Vec<usize>,
+        /// The id of the node
+        id: Id,
+        /// The encoded node
+        bytes: Vec<u8>,
+    },
+    /// A node only present in the old tree
+    Removed {
+        /// The location of the node
+        path: Vec<usize>,
+        /// The id of the node
+        id: Id,
+    },
+    /// A node present in both trees with different contents, with its new
+    /// encoding
+    Changed {
+        /// The location of the node
+        path: Vec<usize>,
+        /// The id of the node in the old tree
+        old: Id,
+        /// The id of the node in the new tree
+        new: Id,
+        /// The encoded node in the new tree
+        bytes: Vec<u8>,
+    },
+}
+
+/// The structural difference between two trees
+///
+/// Children are matched by position, subtrees with equal `Id`s are skipped.
+/// The diff carries every new node, so it can be applied as a patch to a
+/// store holding the old tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diff {
+    old: Id,
+    new: Id,
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Walks the trees under `old` and `new`, both of which have to be in
+    /// the store, through nodes of type `N`
+    ///
+    /// The children of a node are the `Id`s it decodes as an `N`, inlined or
+    /// not. Returns `CanonError::InvalidEncoding` if a node is not an `N`.
+    /// Changes are ordered depth first, parents before their children.
+    pub fn new<N: Canon>(old: &Id, new: &Id) -> Result<Self, CanonError> {
+        let mut changes = Vec::new();
+        let mut stack = vec![(Vec::new(), Some(*old), Some(*new))];
+
+        while let Some((path, old, new)) = stack.pop() {
+            let (old_children, new_children) = match (old, new) {
+                (Some(old), Some(new)) if old == new => continue,
+                (Some(old), Some(new)) => {
+                    let old_bytes = old.bytes()?;
+                    let bytes = new.bytes()?;
+                    let children = (
+                        walk::decoded_children::<N>(&old_bytes)?,
+                        walk::decoded_children::<N>(&bytes)?,
+                    );
+                    changes.push(Change::Changed {
+                        path: path.clone(),
+                        old,
+                        new,
+                        bytes,
+                    });
+                    children
+                }
+                (None, Some(id)) => {
+                    let bytes = id.bytes()?;
+                    let new_children = walk::decoded_children::<N>(&bytes)?;
+                    changes.push(Change::Added {
+                        path: path.clone(),
+                        id,
+                        bytes,
+                    });
+                    (Vec::new(), new_children)
+                }
+                (Some(id), None) => {
+                    let old_children =
+                        walk::decoded_children::<N>(&id.bytes()?)?;
+                    changes.push(Change::Removed {
+                        path: path.clone(),
+                        id,
+                    });
+                    (old_children, Vec::new())
+                }
+                (None, None) => continue,
+            };
+
+            let count = core::cmp::max(old_children.len(), new_children.len());
+            for index in (0..count).rev() {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                stack.push((
+                    child_path,
+                    old_children.get(index).copied(),
+                    new_children.get(index).copied(),
+                ));
+            }
+        }
+
+        Ok(Diff {
+            old: *old,
+            new: *new,
+            changes,
+        })
+    }
+
+    /// Returns the root of the old tree
+    pub fn old_root(&self) -> Id {
+        self.old
+    }
+
+    /// Returns the root of the new tree
+    pub fn new_root(&self) -> Id {
+        self.new
+    }
+
+    /// Returns the changed nodes
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns true if both trees are identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Puts the nodes of the new tree into the store, returning its root
+    ///
+    /// The store is expected to hold the tree under `old`. Every node is
+    /// checked against its id before anything is written, returning
+    /// `CanonError::HashMismatch` if one does not match, and
+    /// `CanonError::InvalidEncoding` if the store does not support its hash
+    /// algorithm.
+    pub fn apply(&self, old: &Id) -> Result<Id, CanonError> {
+        if *old != self.old {
+            return Err(CanonError::InvalidEncoding);
+        }
+        if old.size() > PAYLOAD_BYTES && !Store::contains(old.payload()) {
+            return Err(CanonError::NotFound);
+        }
+
+        let nodes = self.changes.iter().filter_map(|change| match change {
+            Change::Added { id, bytes, .. } => Some((id, bytes)),
+            Change::Changed { new, bytes, .. } => Some((new, bytes)),
+            Change::Removed { .. } => None,
+        });
+
+        for (id, bytes) in nodes.clone() {
+            if Id::for_bytes(id.algorithm(), bytes) != *id {
+                return Err(CanonError::HashMismatch);
+            }
+            if id.size() > PAYLOAD_BYTES && !Store::supports(id.algorithm()) {
+                return Err(CanonError::InvalidEncoding);
+            }
+        }
+
+        for (id, bytes) in nodes {
+            if id.size() > PAYLOAD_BYTES {
+                Store::put_with(id.algorithm(), bytes)?;
+            }
+        }
+
+        Ok(self.new)
+    }
+}
+
+fn encode_path(path: &[usize], sink: &mut Sink) {
+    (path.len() as u64).encode(sink);
+    for index in path {
+        (*index as u64).encode(sink);
+    }
+}
+
+fn decode_path(source: &mut Source) -> Result<Vec<usize>, CanonError> {
+    let len = u64::decode(source)?;
+    let mut path = Vec::new();
+    for _ in 0..len {
+        path.push(u64::decode(source)? as usize);
+    }
+    Ok(path)
+}
+
+fn path_len(path: &[usize]) -> usize {
+    path.iter()
+        .map(|index| (*index as u64).encoded_len())
+        .sum::<usize>()
+        + (path.len() as u64).encoded_len()
+}
+
+impl Canon for Change {
+    fn encode(&self, sink: &mut Sink) {
+        match self {
+            Change::Added { path, id, bytes } => {
+                0u8.encode(sink);
+                encode_path(path, sink);
+                id.encode(sink);
+                bytes.encode(sink);
+            }
+            Change::Removed { path, id } => {
+                1u8.encode(sink);
+                encode_path(path, sink);
+                id.encode(sink);
+            }
+            Change::Changed {
+                path,
+                old,
+                new,
+                bytes,
+            } => {
+                2u8.encode(sink);
+                encode_path(path, sink);
+                old.encode(sink);
+                new.encode(sink);
+                bytes.encode(sink);
+            }
+        }
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        match u8::decode(source)? {
+            0 => Ok(Change::Added {
+                path: decode_path(source)?,
+                id: Id::decode(source)?,
+                bytes: Vec::decode(source)?,
+            }),
+            1 => Ok(Change::Removed {
+                path: decode_path(source)?,
+                id: Id::decode(source)?,
+            }),
+            2 => Ok(Change::Changed {
+                path: decode_path(source)?,
+                old: Id::decode(source)?,
+                new: Id::decode(source)?,
+                bytes: Vec::decode(source)?,
+            }),
+            _ => Err(CanonError::InvalidEncoding),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            Change::Added { path, id, bytes } => {
+                path_len(path) + id.encoded_len() + bytes.encoded_len()
+            }
+            Change::Removed { path, id } => path_len(path) + id.encoded_len(),
+            Change::Changed {
+                path,
+                old,
+                new,
+                bytes,
+            } => {
+                path_len(path)
+                    + old.encoded_len()
+                    + new.encoded_len()
+                    + bytes.encoded_len()
+            }
+        }
+    }
+}
+
+impl Canon for Diff {
+    fn encode(&self, sink: &mut Sink) {
+        self.old.encode(sink);
+        self.new.encode(sink);
+        self.changes.encode(sink);
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        Ok(Diff {
+            old: Id::decode(source)?,
+            new: Id::decode(source)?,
+            changes: Vec::decode(source)?,
+        })
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.old.encoded_len()
+            + self.new.encoded_len()
+            + self.changes.encoded_len()
+    }
+}
@@ -0,0 +1,68 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use blake2b_simd::Params;
+use sha2::{Digest, Sha256};
+
+use crate::id::IdHash;
+
+/// The hash function used for the payload of an `Id`
+///
+/// The algorithm is recorded in the version byte of every `Id`, so values
+/// hashed with different algorithms can live in the same store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HashAlgorithm {
+    /// 32 byte Blake2b
+    #[default]
+    Blake2b = 0,
+    /// SHA-256
+    Sha256 = 1,
+    /// BLAKE3
+    Blake3 = 2,
+}
+
+impl HashAlgorithm {
+    /// Every supported algorithm
+    pub const ALL: [HashAlgorithm; 3] = [
+        HashAlgorithm::Blake2b,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+    ];
+
+    /// Returns the algorithm recorded in an `Id` version byte
+    pub fn from_version(version: u8) -> Option<Self> {
+        Self::ALL.get(version as usize).copied()
+    }
+
+    /// Returns the `Id` version byte of the algorithm
+    pub const fn version(self) -> u8 {
+        self as u8
+    }
+
+    /// Hash a slice of bytes
+    pub fn hash(self, bytes: &[u8]) -> IdHash {
+        match self {
+            HashAlgorithm::Blake2b => {
+                let mut state = Params::new().hash_length(32).to_state();
+                state.update(bytes);
+
+                let mut buf = IdHash::default();
+                buf.copy_from_slice(state.finalize().as_ref());
+                buf
+            }
+            HashAlgorithm::Sha256 => Sha256::digest(bytes).into(),
+            HashAlgorithm::Blake3 => blake3::hash(bytes).into(),
+        }
+    }
+
+    /// Returns the algorithm under which `bytes` hash to `hash`, if any
+    pub fn detect(bytes: &[u8], hash: &IdHash) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.hash(bytes) == *hash)
+    }
+}
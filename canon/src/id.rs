@@ -6,11 +6,10 @@
 
 use alloc::vec::Vec;
 
-use crate::canon::{Canon, CanonError, EncodeToVec};
+use crate::canon::{Canon, CanonError};
+use crate::hash::HashAlgorithm;
 use crate::store::{Sink, Source, Store};
 
-const VERSION: u8 = 0;
-
 /// The size of the Id payload, used to store cryptographic hashes or inlined
 /// values
 pub const PAYLOAD_BYTES: usize = 32;
@@ -45,30 +44,78 @@ pub struct Id {
 }
 
 impl Id {
-    /// Creates a new Id from a type
+    /// Creates a new Id from a type, hashed with the algorithm selected by
+    /// `Store::set_hash_algorithm`
+    ///
+    /// If the type has a `Canon::TAG`, the tag is prefixed to its encoding.
     pub fn new<T>(t: &T) -> Self
     where
         T: Canon,
     {
-        let len = t.encoded_len();
-        let payload = if len > PAYLOAD_BYTES {
-            Store::put(&t.encode_to_vec())
+        let bytes = Self::tagged_to_vec(t);
+        let len = bytes.len();
+        let (algorithm, payload) = if len > PAYLOAD_BYTES {
+            Store::put_selected(&bytes)
         } else {
-            let mut stack_buf = Inlined::default();
-            let mut sink = Sink::new(&mut stack_buf[..len]);
-            t.encode(&mut sink);
-            stack_buf
+            let mut inlined = Inlined::default();
+            inlined[..len].copy_from_slice(&bytes);
+            (Store::hash_algorithm(), inlined)
         };
 
         assert!(len <= u32::MAX as usize, "Payload length overflow");
 
         Id {
-            version: VERSION,
+            version: algorithm.version(),
             len: (len as u32),
             payload,
         }
     }
 
+    // Constructs an Id from its parts, as found in an encoding
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_parts(
+        version: u8,
+        len: u32,
+        payload: Payload,
+    ) -> Option<Self> {
+        HashAlgorithm::from_version(version)?;
+        Some(Id {
+            version,
+            len,
+            payload,
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn version(&self) -> u8 {
+        self.version
+    }
+
+    // Computes the Id of already encoded bytes, without storing them
+    pub(crate) fn for_bytes(algorithm: HashAlgorithm, bytes: &[u8]) -> Self {
+        let len = bytes.len();
+        assert!(len <= u32::MAX as usize, "Payload length overflow");
+
+        let mut payload = Payload::default();
+        if len > PAYLOAD_BYTES {
+            payload = Store::hash_with(algorithm, bytes);
+        } else {
+            payload[..len].copy_from_slice(bytes);
+        }
+
+        Id {
+            version: algorithm.version(),
+            len: len as u32,
+            payload,
+        }
+    }
+
+    /// Returns the algorithm the value is hashed with
+    pub fn algorithm(&self) -> HashAlgorithm {
+        // only valid versions are ever constructed
+        HashAlgorithm::from_version(self.version).unwrap_or_default()
+    }
+
     /// Returns the computed hash of the value.
     ///
     /// Note that this is different from the payload itself in case of an
@@ -81,7 +128,7 @@ impl Id {
         if len > PAYLOAD_BYTES {
             self.payload
         } else {
-            Store::hash(&self.payload[0..len])
+            Store::hash_with(self.algorithm(), &self.payload[0..len])
         }
     }
 
@@ -106,24 +153,25 @@ impl Id {
         T: Canon,
     {
         let len = self.size();
-        // this does not yet allocate
-        let mut buf = Vec::new();
+        let buf;
 
         let mut source = if len > PAYLOAD_BYTES {
-            // allocation happens here
-            buf.resize_with(len, || 0);
-
-            Store::get(&self.payload, &mut buf)?;
+            // the length is checked against the store before allocating, as
+            // the Id may have been decoded from untrusted bytes
+            buf = Store::get_sized(&self.payload, len, Some(self.algorithm()))?;
             Source::new(&buf)
         } else {
             Source::new(&self.payload[..len])
         };
 
-        T::decode(&mut source)
+        Self::decode_tagged(&mut source)
     }
 
     /// Takes the bytes corresponding to this id out of the underlying store.
     ///
+    /// The bytes are only removed from the store once every reference to
+    /// them has been taken or released.
+    ///
     /// If the Id is inlined, this is a no-op and returns Ok(None)
     pub fn take_bytes(&self) -> Result<Option<Vec<u8>>, CanonError> {
         if self.size() <= PAYLOAD_BYTES {
@@ -133,6 +181,66 @@ impl Id {
         }
     }
 
+    /// Drops the reference this id holds to its bytes in the underlying
+    /// store.
+    ///
+    /// If the Id is inlined, this is a no-op
+    pub fn release(&self) -> Result<(), CanonError> {
+        if self.size() <= PAYLOAD_BYTES {
+            Ok(())
+        } else {
+            Store::release(&self.payload)
+        }
+    }
+
+    // Returns the bytes behind the Id, fetched from the store unless they are
+    // inlined
+    pub(crate) fn bytes(&self) -> Result<Vec<u8>, CanonError> {
+        let len = self.size();
+        if len > PAYLOAD_BYTES {
+            Store::get_sized(&self.payload, len, Some(self.algorithm()))
+        } else {
+            Ok(Vec::from(&self.payload[..len]))
+        }
+    }
+
+    // Returns the length of the bytes behind the Id of `t`, its encoding
+    // prefixed with the tag of its type
+    pub(crate) fn tagged_len<T: Canon>(t: &T) -> usize {
+        let tag_len = match T::TAG {
+            Some(tag) => (tag.len() as u32).encoded_len() + tag.len(),
+            None => 0,
+        };
+        tag_len + t.encoded_len()
+    }
+
+    pub(crate) fn encode_tagged<T: Canon>(t: &T, sink: &mut Sink) {
+        if let Some(tag) = T::TAG {
+            (tag.len() as u32).encode(sink);
+            sink.copy_bytes(tag.as_bytes());
+        }
+        t.encode(sink);
+    }
+
+    pub(crate) fn tagged_to_vec<T: Canon>(t: &T) -> Vec<u8> {
+        let mut vec = Vec::new();
+        Self::encode_tagged(t, &mut Sink::growable(&mut vec));
+        vec
+    }
+
+    // Decodes a value behind an Id, failing if it was tagged differently
+    pub(crate) fn decode_tagged<T: Canon>(
+        source: &mut Source,
+    ) -> Result<T, CanonError> {
+        if let Some(tag) = T::TAG {
+            let len = u32::decode(source)? as usize;
+            if len != tag.len() || source.read_bytes(len)? != tag.as_bytes() {
+                return Err(CanonError::InvalidEncoding);
+            }
+        }
+        T::decode(source)
+    }
+
     // This is a conveniance function to be called from Repr, in order not to
     // have to construct an Id to get the encoded_len correctly.
     pub(crate) fn encoded_len_for_payload_len(payload_len: usize) -> usize {
@@ -153,7 +261,7 @@ impl Canon for Id {
     fn decode(source: &mut Source) -> Result<Self, CanonError> {
         let version = u8::decode(source)?;
 
-        if version != 0 {
+        if HashAlgorithm::from_version(version).is_none() {
             return Err(CanonError::InvalidEncoding);
         }
 
@@ -163,13 +271,15 @@ impl Canon for Id {
         let payload_size = core::cmp::min(len as usize, PAYLOAD_BYTES);
 
         payload[..payload_size]
-            .copy_from_slice(source.read_bytes(payload_size));
+            .copy_from_slice(source.read_bytes(payload_size)?);
 
-        Ok(Id {
+        let id = Id {
             version,
             len,
             payload,
-        })
+        };
+        source.record(id);
+        Ok(id)
     }
 
     fn encoded_len(&self) -> usize {
@@ -18,7 +18,7 @@ impl Canon for u8 {
 
     fn decode(source: &mut Source) -> Result<Self, CanonError> {
         let mut bytes = [0u8; 1];
-        bytes.copy_from_slice(source.read_bytes(1));
+        bytes.copy_from_slice(source.read_bytes(1)?);
         Ok(u8::from_be_bytes(bytes))
     }
 
@@ -44,15 +44,20 @@ macro_rules! varint {
 
             fn decode(source: &mut Source) -> Result<Self, $crate::CanonError> {
                 const MSB: u8 = 0b1000_0000;
-                let varint_len = source.bytes[source.offset..]
-                    .iter()
-                    .take_while(|b| *b & MSB != 0)
-                    .count()
-                    + 1;
-                VarInt::decode_var(source.read_bytes(varint_len))
-                    .map_or(Err(CanonError::InvalidEncoding), |(number, _)| {
-                        Ok(number)
-                    })
+                const BUFSIZE: usize = mem::size_of::<$varint>() * 8 / 7 + 1;
+                let mut buf = [0u8; BUFSIZE];
+                // read a byte at a time, the last one having the MSB unset
+                for len in 1..=BUFSIZE {
+                    let byte = source.read_bytes(1)?[0];
+                    buf[len - 1] = byte;
+                    if byte & MSB == 0 {
+                        return VarInt::decode_var(&buf[..len]).map_or(
+                            Err(CanonError::InvalidEncoding),
+                            |(number, _)| Ok(number),
+                        );
+                    }
+                }
+                Err(CanonError::InvalidEncoding)
             }
 
             fn encoded_len(&self) -> usize {
@@ -127,7 +132,7 @@ impl Canon for bool {
     }
 
     fn decode(source: &mut Source) -> Result<Self, CanonError> {
-        match source.read_bytes(1) {
+        match source.read_bytes(1)? {
             [0] => Ok(false),
             [1] => Ok(true),
             _ => Err(CanonError::InvalidEncoding),
@@ -154,7 +159,7 @@ where
     }
 
     fn decode(source: &mut Source) -> Result<Self, CanonError> {
-        match source.read_bytes(1) {
+        match source.read_bytes(1)? {
             [0] => Ok(None),
             [1] => Ok(Some(T::decode(source)?)),
             _ => Err(CanonError::InvalidEncoding),
@@ -188,7 +193,7 @@ where
     }
 
     fn decode(source: &mut Source) -> Result<Self, CanonError> {
-        match source.read_bytes(1) {
+        match source.read_bytes(1)? {
             [0] => Ok(Ok(T::decode(source)?)),
             [1] => Ok(Err(E::decode(source)?)),
             _ => Err(CanonError::InvalidEncoding),
@@ -342,13 +347,13 @@ mod alloc_impls {
 
         fn decode(source: &mut Source) -> Result<Self, CanonError> {
             let len = u64::decode(source)?;
-            let vec: Vec<u8> = source.read_bytes(len as usize).into();
+            let vec: Vec<u8> = source.read_bytes(len as usize)?.into();
             String::from_utf8(vec).map_err(|_| CanonError::InvalidEncoding)
         }
 
         fn encoded_len(&self) -> usize {
             let len = self.len() as u64;
-            len.encoded_len() + self.as_bytes().len()
+            len.encoded_len() + self.len()
         }
     }
 
@@ -19,12 +19,31 @@
 extern crate alloc;
 
 mod canon;
+mod diff;
+mod hash;
 mod id;
 mod implementations;
+mod proof;
 mod repr;
 mod store;
 
 pub use canon::{Canon, CanonError, EncodeToVec};
+pub use diff::{Change, Diff};
+pub use hash::HashAlgorithm;
 pub use id::{Id, IdHash};
+pub use proof::Proof;
 pub use repr::{Repr, Val, ValMut};
-pub use store::{Sink, Source, Store};
+pub use store::{
+    BridgeStatus, GcStats, Sink, Source, Store, StoreBackend, StoreObserver,
+    StoreScope, StoreStats, Transaction, BRIDGE_ABI_VERSION,
+    MIN_BRIDGE_ABI_VERSION,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use canon::{DecodeFromReader, EncodeToWriter};
+#[cfg(any(target_arch = "wasm32", feature = "bridge-mock"))]
+pub use store::BridgeStore;
+#[cfg(not(target_arch = "wasm32"))]
+pub use store::{
+    ChannelTransport, DiskStore, HostStore, Message, SharedStore, Transport,
+};
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec::Vec;
+
+use crate::canon::{Canon, CanonError};
+use crate::id::Id;
+use crate::store::{walk, Sink, Source};
+
+// A node on the path, with the position of the next node down the path
+// among the `Id`s the node decodes
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Level {
+    node: Vec<u8>,
+    index: u32,
+}
+
+impl Canon for Level {
+    fn encode(&self, sink: &mut Sink) {
+        self.node.encode(sink);
+        self.index.encode(sink);
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        Ok(Level {
+            node: Vec::decode(source)?,
+            index: u32::decode(source)?,
+        })
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.node.encoded_len() + self.index.encoded_len()
+    }
+}
+
+/// A proof that a value is contained in the tree under a root `Id`
+///
+/// The proof holds the encoded nodes along the path from the root to the
+/// value, including the `Id`s of their other children, so it can be checked
+/// without access to the store holding the tree.
+///
+/// Every node on the path is of the node type `N` given to `new` and
+/// `verify`, and its children are the `Id`s its encoding decodes as, such as
+/// the `Repr`s of `N`. Data that merely contains the bytes of an encoded
+/// `Id` is never taken for a child, so the proof cannot point into it.
+/// Inlined values of 32 bytes or less are children like any other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    // ordered from the root down
+    levels: Vec<Level>,
+}
+
+impl Proof {
+    /// Creates a proof for the value found by following `path` from `root`,
+    /// through nodes of type `N`
+    ///
+    /// Each element of the path selects a child of the current node, by its
+    /// position among the `Id`s the node decodes. Returns
+    /// `CanonError::NotFound` if a node on the path is not in the store or
+    /// has no such child, and `CanonError::InvalidEncoding` if it is not an
+    /// `N`.
+    pub fn new<N: Canon>(
+        root: &Id,
+        path: &[usize],
+    ) -> Result<Self, CanonError> {
+        let mut levels = Vec::with_capacity(path.len());
+        let mut id = *root;
+
+        for index in path {
+            let node = id.bytes()?;
+            let child = walk::decoded_children::<N>(&node)?
+                .get(*index)
+                .copied()
+                .ok_or(CanonError::NotFound)?;
+
+            levels.push(Level {
+                node,
+                index: *index as u32,
+            });
+            id = child;
+        }
+
+        Ok(Proof { levels })
+    }
+
+    /// Returns the number of levels between the root and the value
+    pub fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Checks that `value` is contained under `root`, through nodes of type
+    /// `N`
+    ///
+    /// Each node is decoded as an `N` to find the `Id` of the child at the
+    /// recorded position, which has to be the `Id` of the node below. The
+    /// `Id`s are recomputed bottom-up with `Store::hash_with` and the
+    /// algorithm each parent records for its child.
+    pub fn verify<N: Canon, T: Canon>(&self, root: &Id, value: &T) -> bool {
+        let value = Id::tagged_to_vec(value);
+        let mut bytes = &value[..];
+
+        for level in self.levels.iter().rev() {
+            let child = match walk::decoded_children::<N>(&level.node) {
+                Ok(children) => children.get(level.index as usize).copied(),
+                Err(_) => None,
+            };
+            let child = match child {
+                Some(child) => child,
+                None => return false,
+            };
+
+            if Id::for_bytes(child.algorithm(), bytes) != child {
+                return false;
+            }
+            bytes = &level.node;
+        }
+
+        Id::for_bytes(root.algorithm(), bytes) == *root
+    }
+}
+
+impl Canon for Proof {
+    fn encode(&self, sink: &mut Sink) {
+        self.levels.encode(sink)
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        Ok(Proof {
+            levels: Vec::decode(source)?,
+        })
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.levels.encoded_len()
+    }
+}
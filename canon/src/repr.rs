@@ -11,21 +11,16 @@ use alloc::rc::Rc;
 
 use crate::{Canon, CanonError, Id, Sink, Source};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 enum ReprInner<T> {
     Id(Id),
     IdValue(Id, Rc<T>),
     Value(Rc<T>),
     // Used for moving ReprInner out of the RefCell
+    #[default]
     Placeholder,
 }
 
-impl<T> Default for ReprInner<T> {
-    fn default() -> Self {
-        ReprInner::Placeholder
-    }
-}
-
 impl<T> Clone for ReprInner<T> {
     fn clone(&self) -> Self {
         match self {
@@ -65,7 +60,7 @@ where
                 ReprInner::IdValue(id, rc)
             }
             ReprInner::Value(rc) => {
-                let t: &T = &*rc;
+                let t: &T = &rc;
                 let id = Id::new(t);
                 id.encode(sink);
                 ReprInner::IdValue(id, rc)
@@ -84,7 +79,7 @@ where
         match &*self.0.borrow() {
             ReprInner::Id(id) | ReprInner::IdValue(id, _) => id.encoded_len(),
             ReprInner::Value(rc) => {
-                let enc_len = (*rc).encoded_len();
+                let enc_len = Id::tagged_len(&**rc);
                 Id::encoded_len_for_payload_len(enc_len)
             }
             ReprInner::Placeholder => unreachable!(),
@@ -101,7 +96,7 @@ impl<'a, T> Deref for Val<'a, T> {
 
     fn deref(&self) -> &Self::Target {
         match &*self.0 {
-            ReprInner::Value(rc) | ReprInner::IdValue(_, rc) => &*rc,
+            ReprInner::Value(rc) | ReprInner::IdValue(_, rc) => rc,
             _ => unreachable!("Invalid typestate"),
         }
     }
@@ -116,7 +111,7 @@ impl<'a, T> Deref for ValMut<'a, T> {
 
     fn deref(&self) -> &T {
         match &*self.0 {
-            ReprInner::Value(rc) => &*rc,
+            ReprInner::Value(rc) => rc,
             _ => panic!("Broken typestate guarantee"),
         }
     }
@@ -141,7 +136,7 @@ impl<T> Repr<T> {
     }
 
     /// Retrieve the value behind this representation
-    pub fn val(&self) -> Result<Val<T>, CanonError>
+    pub fn val(&self) -> Result<Val<'_, T>, CanonError>
     where
         T: Canon,
     {
@@ -162,7 +157,7 @@ impl<T> Repr<T> {
     }
 
     /// Retrieve a mutable value behind this representation
-    pub fn val_mut(&mut self) -> Result<ValMut<T>, CanonError>
+    pub fn val_mut(&mut self) -> Result<ValMut<'_, T>, CanonError>
     where
         T: Canon,
     {
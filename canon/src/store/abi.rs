@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use crate::canon::CanonError;
+
+/// Version of the `canon` wasm import module `BridgeStore` expects
+///
+/// Hosts report the version they implement through the `canon.abi_version`
+/// import. Version 1 is the original module, without status codes, and
+/// version 2 added them to `get`, `put`, `put_with`, `put_many` and
+/// `get_many`.
+///
+/// Version 3 imports `abi_version`, `put`, `get`, `put_many`, `get_many`,
+/// `take`, `remove`, `refs`, `hash`, `size`, `len` and `clear`. Guests no
+/// longer import `put_with`, storing single values through `put_many`.
+pub const BRIDGE_ABI_VERSION: u32 = 3;
+
+/// Oldest host version `BridgeStore` works with
+///
+/// Later versions of the module only add imports, so hosts reporting any
+/// version from this one on are accepted.
+pub const MIN_BRIDGE_ABI_VERSION: u32 = 3;
+
+/// Status code returned by the fallible `canon` imports
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BridgeStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A value was not found in the host store
+    NotFound = 1,
+    /// The host could not use the arguments it was passed
+    InvalidEncoding = 2,
+}
+
+impl BridgeStatus {
+    /// Returns the status of a code, unknown codes being invalid
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => BridgeStatus::Ok,
+            1 => BridgeStatus::NotFound,
+            _ => BridgeStatus::InvalidEncoding,
+        }
+    }
+
+    /// Returns the code passed over the bridge
+    pub const fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Returns the status of the result of a host operation
+    pub fn from_result<T>(result: &Result<T, CanonError>) -> Self {
+        match result {
+            Ok(_) => BridgeStatus::Ok,
+            Err(CanonError::NotFound) => BridgeStatus::NotFound,
+            Err(_) => BridgeStatus::InvalidEncoding,
+        }
+    }
+
+    /// Converts the status into a result
+    pub fn into_result(self) -> Result<(), CanonError> {
+        match self {
+            BridgeStatus::Ok => Ok(()),
+            BridgeStatus::NotFound => Err(CanonError::NotFound),
+            BridgeStatus::InvalidEncoding => Err(CanonError::InvalidEncoding),
+        }
+    }
+}
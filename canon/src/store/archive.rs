@@ -0,0 +1,178 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! A self-contained file format for merkle DAGs.
+//!
+//! ```text
+//! magic    b"canonar" and a format version byte
+//! roots    u32 count, then per root its version byte, u32 length and the
+//!          32 byte payload
+//! refs     u32 count, then per ref the u32 length of its name, the name
+//!          and the id it points to, written like a root
+//! blobs    until the end of the archive: the 32 byte hash, the u32 length
+//!          and the bytes of each stored value reachable from the roots
+//! ```
+//!
+//! All integers are little endian.
+
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+
+use crate::id::{Id, IdHash, Payload, PAYLOAD_BYTES};
+use crate::store::{walk, Store};
+
+const MAGIC: &[u8; 7] = b"canonar";
+const FORMAT_VERSION: u8 = 0;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn export<W: Write>(
+    roots: &[Id],
+    refs: &[(String, Id)],
+    mut writer: W,
+) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[FORMAT_VERSION])?;
+
+    writer.write_all(&(roots.len() as u32).to_le_bytes())?;
+    for root in roots {
+        write_id(&mut writer, root)?;
+    }
+
+    writer.write_all(&(refs.len() as u32).to_le_bytes())?;
+    for (name, id) in refs {
+        writer.write_all(&(name.len() as u32).to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+        write_id(&mut writer, id)?;
+    }
+
+    let mut written = BTreeSet::new();
+    let mut stack: Vec<Id> = roots
+        .iter()
+        .chain(refs.iter().map(|(_, id)| id))
+        .filter(|id| id.size() > PAYLOAD_BYTES)
+        .copied()
+        .collect();
+
+    while let Some(id) = stack.pop() {
+        let hash = *id.payload();
+        if !written.insert(hash) {
+            continue;
+        }
+
+        let mut bytes = vec![0u8; id.size()];
+        Store::get(&hash, &mut bytes).map_err(|_| {
+            io::Error::new(io::ErrorKind::NotFound, "Value not in store")
+        })?;
+
+        writer.write_all(&hash)?;
+        writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+
+        stack.extend(walk::child_ids(&bytes, Store::contains));
+    }
+
+    writer.flush()
+}
+
+pub(crate) fn import<R: Read>(mut reader: R) -> io::Result<Vec<Id>> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic[..7] != MAGIC || magic[7] != FORMAT_VERSION {
+        return Err(invalid_data("Not a canonical archive"));
+    }
+
+    let count = read_u32(&mut reader)?;
+    let mut roots = Vec::new();
+    for _ in 0..count {
+        roots.push(read_id(&mut reader)?);
+    }
+
+    let count = read_u32(&mut reader)?;
+    let mut refs = Vec::new();
+    for _ in 0..count {
+        let len = read_u32(&mut reader)?;
+        let mut name = Vec::new();
+        reader.by_ref().take(len as u64).read_to_end(&mut name)?;
+
+        if name.len() != len as usize {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let name = String::from_utf8(name)
+            .map_err(|_| invalid_data("Invalid ref name"))?;
+        refs.push((name, read_id(&mut reader)?));
+    }
+
+    let mut hash = IdHash::default();
+    while read_hash(&mut reader, &mut hash)? {
+        let len = read_u32(&mut reader)? as usize;
+        let mut bytes = Vec::new();
+        reader.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let algorithm = Store::detect(&bytes, &hash)
+            .ok_or_else(|| invalid_data("Hash mismatch for archived value"))?;
+
+        Store::put_with(algorithm, &bytes).map_err(|_| {
+            invalid_data("Hash algorithm not supported by the store")
+        })?;
+    }
+
+    for root in roots.iter().chain(refs.iter().map(|(_, id)| id)) {
+        if root.size() > PAYLOAD_BYTES && !Store::contains(root.payload()) {
+            return Err(invalid_data("Archive is missing a root value"));
+        }
+    }
+
+    for (name, id) in refs {
+        Store::set_ref(&name, id);
+    }
+
+    Ok(roots)
+}
+
+fn write_id<W: Write>(writer: &mut W, id: &Id) -> io::Result<()> {
+    writer.write_all(&[id.version()])?;
+    writer.write_all(&(id.size() as u32).to_le_bytes())?;
+    writer.write_all(id.payload())
+}
+
+fn read_id<R: Read>(reader: &mut R) -> io::Result<Id> {
+    let mut version = [0u8; 1];
+    reader.read_exact(&mut version)?;
+    let len = read_u32(reader)?;
+    let mut payload = Payload::default();
+    reader.read_exact(&mut payload)?;
+
+    Id::from_parts(version[0], len, payload)
+        .ok_or_else(|| invalid_data("Invalid root id"))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+// Reads the hash starting the next blob, returning false at the end of the
+// archive
+fn read_hash<R: Read>(reader: &mut R, hash: &mut IdHash) -> io::Result<bool> {
+    let mut read = 0;
+    while read < hash.len() {
+        match reader.read(&mut hash[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,324 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash};
+
+/// A content-addressed storage engine that `Store` routes its calls to.
+///
+/// Implementations are installed per thread with `Store::install`, after
+/// which `Id::new`, `Id::reify` and `Repr` use them transparently.
+pub trait StoreBackend {
+    /// Write the byte slice into the backend and return its hash
+    fn put(&self, bytes: &[u8]) -> IdHash;
+
+    /// Write the byte slice into the backend, returning its hash and whether
+    /// the backend did not hold it before
+    ///
+    /// The default implementation checks `contains` before the `put`.
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        let new = !self.contains(&self.hash(bytes));
+        (self.put(bytes), new)
+    }
+
+    /// Returns true if the backend stores values hashed with the given
+    /// algorithm
+    ///
+    /// The default implementation only supports Blake2b.
+    fn supports(&self, algorithm: HashAlgorithm) -> bool {
+        algorithm == HashAlgorithm::Blake2b
+    }
+
+    /// Write the byte slice into the backend under its hash with the given
+    /// algorithm, returning the hash and whether the backend did not hold it
+    /// before
+    ///
+    /// Fails with `CanonError::InvalidEncoding` if the algorithm is not
+    /// supported. The default implementation only supports Blake2b, calling
+    /// `insert`.
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        match algorithm {
+            HashAlgorithm::Blake2b => Ok(self.insert(bytes)),
+            _ => Err(CanonError::InvalidEncoding),
+        }
+    }
+
+    /// Write every byte slice into the backend under its hash with the given
+    /// algorithm, returning the hash of each and whether the backend did not
+    /// hold it before
+    ///
+    /// The default implementation calls `insert_with` for each value in turn.
+    fn insert_many(
+        &self,
+        algorithm: HashAlgorithm,
+        values: &[&[u8]],
+    ) -> Result<Vec<(IdHash, bool)>, CanonError> {
+        values
+            .iter()
+            .map(|bytes| self.insert_with(algorithm, bytes))
+            .collect()
+    }
+
+    /// Get data with the corresponding hash and write it to a buffer
+    ///
+    /// Returns `CanonError::InvalidEncoding` if the buffer is not of the
+    /// length of the stored data
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError>;
+
+    /// Get the data for every hash, writing each into the buffer at the
+    /// same position, and return the result of each lookup
+    ///
+    /// The default implementation calls `get` for each hash in turn.
+    fn get_many(
+        &self,
+        hashes: &[IdHash],
+        into: &mut [&mut [u8]],
+    ) -> Vec<Result<(), CanonError>> {
+        hashes
+            .iter()
+            .zip(into.iter_mut())
+            .map(|(hash, buf)| self.get(hash, buf))
+            .collect()
+    }
+
+    /// Hash a slice of bytes
+    ///
+    /// Defaults to a 32 byte Blake2b hash
+    fn hash(&self, bytes: &[u8]) -> IdHash {
+        HashAlgorithm::Blake2b.hash(bytes)
+    }
+
+    /// Hash a slice of bytes with the given algorithm
+    ///
+    /// The default implementation calls `hash` for Blake2b.
+    fn hash_with(&self, algorithm: HashAlgorithm, bytes: &[u8]) -> IdHash {
+        match algorithm {
+            HashAlgorithm::Blake2b => self.hash(bytes),
+            other => other.hash(bytes),
+        }
+    }
+
+    /// Takes the bytes corresponding to the id out of the backend, dropping
+    /// a reference to them
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError>;
+
+    /// Drops a reference to the value with the given hash, freeing it once
+    /// no references remain.
+    ///
+    /// Backends that do not count references keep the value.
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        if self.contains(hash) {
+            Ok(())
+        } else {
+            Err(CanonError::NotFound)
+        }
+    }
+
+    /// Returns the number of references held to the value with the given
+    /// hash, 0 if the backend does not hold it
+    ///
+    /// Backends that do not count references never free a value on
+    /// `release`, and report `usize::MAX` for the values they hold.
+    fn refs(&self, hash: &IdHash) -> usize {
+        if self.contains(hash) {
+            usize::MAX
+        } else {
+            0
+        }
+    }
+
+    /// Returns true if the backend holds data for the given hash
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.size_of(hash).is_some()
+    }
+
+    /// Returns the length of the data stored for the given hash
+    fn size_of(&self, hash: &IdHash) -> Option<usize>;
+
+    /// Returns the number of values in the backend
+    fn len(&self) -> usize;
+
+    /// Returns true if the backend holds no values
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every value and every ref from the backend
+    fn clear(&self);
+
+    /// Returns the id the named ref points to
+    ///
+    /// Backends without refs hold none, the default implementations of the
+    /// ref methods changing nothing.
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        let _ = name;
+        None
+    }
+
+    /// Points the named ref at `id`, deleting it if `id` is `None`, and
+    /// returns the id it pointed to before
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        let _ = (name, id);
+        None
+    }
+
+    /// Points the named ref at `new` if it currently points at `current`
+    ///
+    /// On mismatch nothing is changed and the id the ref actually points to
+    /// is returned as the error. The default implementation always fails.
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        let _ = (name, current, new);
+        Err(None)
+    }
+
+    /// Returns every ref with the id it points to, ordered by name
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        Vec::new()
+    }
+
+    /// Removes every value that is not reachable from `roots` or from a ref,
+    /// following the `Id`s encoded in stored values.
+    ///
+    /// Backends that cannot enumerate their contents keep everything and
+    /// report nothing as freed.
+    fn collect_garbage(&self, roots: &[Id]) -> GcStats {
+        let _ = roots;
+        GcStats::default()
+    }
+}
+
+/// What was freed by a garbage collection
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct GcStats {
+    /// The number of values removed from the store
+    pub entries: usize,
+    /// The number of bytes these values occupied
+    pub bytes: usize,
+}
+
+impl<B> StoreBackend for Rc<B>
+where
+    B: StoreBackend + ?Sized,
+{
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        (**self).put(bytes)
+    }
+
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        (**self).insert(bytes)
+    }
+
+    fn supports(&self, algorithm: HashAlgorithm) -> bool {
+        (**self).supports(algorithm)
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        (**self).insert_with(algorithm, bytes)
+    }
+
+    fn insert_many(
+        &self,
+        algorithm: HashAlgorithm,
+        values: &[&[u8]],
+    ) -> Result<Vec<(IdHash, bool)>, CanonError> {
+        (**self).insert_many(algorithm, values)
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        (**self).get(hash, into)
+    }
+
+    fn get_many(
+        &self,
+        hashes: &[IdHash],
+        into: &mut [&mut [u8]],
+    ) -> Vec<Result<(), CanonError>> {
+        (**self).get_many(hashes, into)
+    }
+
+    fn hash(&self, bytes: &[u8]) -> IdHash {
+        (**self).hash(bytes)
+    }
+
+    fn hash_with(&self, algorithm: HashAlgorithm, bytes: &[u8]) -> IdHash {
+        (**self).hash_with(algorithm, bytes)
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        (**self).take_bytes(id)
+    }
+
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        (**self).release(hash)
+    }
+
+    fn refs(&self, hash: &IdHash) -> usize {
+        (**self).refs(hash)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        (**self).contains(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        (**self).size_of(hash)
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
+
+    fn clear(&self) {
+        (**self).clear()
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        (**self).get_ref(name)
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        (**self).set_ref(name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        (**self).compare_and_swap_ref(name, current, new)
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        (**self).list_refs()
+    }
+
+    fn collect_garbage(&self, roots: &[Id]) -> GcStats {
+        (**self).collect_garbage(roots)
+    }
+}
@@ -7,52 +7,233 @@
 extern crate alloc;
 
 use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
 use crate::id::{Id, IdHash};
+use crate::store::{BridgeStatus, StoreBackend, MIN_BRIDGE_ABI_VERSION};
+use alloc::vec;
 use alloc::vec::Vec;
 
 /// Store usable across ffi-boundraries
+///
+/// Refs are not available over the bridge, the store holding none. Clearing
+/// the store clears the whole host store.
 #[derive(Clone, Copy, Default, Debug)]
 pub struct BridgeStore;
 
 impl BridgeStore {
-    pub(crate) fn put(bytes: &[u8]) -> IdHash {
+    /// Returns the version of the `canon` import module the host implements
+    pub fn host_abi_version() -> u32 {
+        unsafe { abi_version() as u32 }
+    }
+
+    /// Checks the host implements a version of the `canon` imports this
+    /// store works with, returning the version it reports otherwise
+    ///
+    /// Hosts predating the versioning lack the `abi_version` import
+    /// altogether, and already fail to instantiate the module.
+    pub fn check_abi() -> Result<(), u32> {
+        let version = Self::host_abi_version();
+        if version >= MIN_BRIDGE_ABI_VERSION {
+            Ok(())
+        } else {
+            Err(version)
+        }
+    }
+}
+
+// Puts can not fail through `StoreBackend`, as with a failing disk
+fn expect_stored(status: i32) {
+    if let Err(err) = BridgeStatus::from_code(status).into_result() {
+        panic!("Failed putting a value in the host store: {:?}", err);
+    }
+}
+
+impl StoreBackend for BridgeStore {
+    fn put(&self, bytes: &[u8]) -> IdHash {
         // we only put larger values here
         debug_assert!(bytes.len() > core::mem::size_of::<IdHash>());
         let mut idhash = IdHash::default();
-        unsafe {
-            put(&bytes[0], bytes.len() as i32, &mut idhash);
-        }
+        expect_stored(unsafe {
+            put(bytes.as_ptr(), bytes.len() as i32, &mut idhash)
+        });
         idhash
     }
 
-    pub fn get(hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
-        // We assume this to always work for the bridge, by catching the error
-        // in the host and aborting before returning.
+    // the host reports the algorithms it does not support
+    fn supports(&self, _algorithm: HashAlgorithm) -> bool {
+        true
+    }
+
+    // values are put with `put_many`, which reports whether they are new
+    // in the same call to the host
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        match self.insert_with(HashAlgorithm::Blake2b, bytes) {
+            Ok(inserted) => inserted,
+            Err(err) => {
+                panic!("Failed putting a value in the host store: {:?}", err)
+            }
+        }
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        debug_assert!(bytes.len() > core::mem::size_of::<IdHash>());
+        Ok(self.insert_many(algorithm, &[bytes])?[0])
+    }
+
+    fn insert_many(
+        &self,
+        algorithm: HashAlgorithm,
+        values: &[&[u8]],
+    ) -> Result<Vec<(IdHash, bool)>, CanonError> {
+        if values.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // the values are passed concatenated, followed by their lengths
+        let lens: Vec<i32> = values.iter().map(|v| v.len() as i32).collect();
+        let bytes: Vec<u8> = values.concat();
+
+        let mut hashes = Vec::new();
+        hashes.resize_with(values.len(), IdHash::default);
+        let mut new = vec![0u8; values.len()];
+
+        let status = unsafe {
+            put_many(
+                algorithm.version() as i32,
+                bytes.as_ptr(),
+                lens.as_ptr(),
+                values.len() as i32,
+                hashes.as_mut_ptr(),
+                new.as_mut_ptr(),
+            )
+        };
+        BridgeStatus::from_code(status).into_result()?;
+
+        Ok(hashes
+            .into_iter()
+            .zip(new.into_iter().map(|n| n != 0))
+            .collect())
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
         let len = into.len();
-        unsafe { Ok(get(&hash, &mut into[0], len as i32)) }
+        let status = unsafe { get(hash, into.as_mut_ptr(), len as i32) };
+        BridgeStatus::from_code(status).into_result()
+    }
+
+    fn get_many(
+        &self,
+        hashes: &[IdHash],
+        into: &mut [&mut [u8]],
+    ) -> Vec<Result<(), CanonError>> {
+        if hashes.is_empty() {
+            return Vec::new();
+        }
+
+        // the host writes all values into one buffer, in order
+        let lens: Vec<i32> = into.iter().map(|b| b.len() as i32).collect();
+        let mut bytes = vec![0u8; into.iter().map(|b| b.len()).sum()];
+        let mut statuses = vec![0i32; hashes.len()];
+
+        unsafe {
+            get_many(
+                hashes.as_ptr(),
+                lens.as_ptr(),
+                hashes.len() as i32,
+                bytes.as_mut_ptr(),
+                statuses.as_mut_ptr(),
+            );
+        }
+
+        let mut offset = 0;
+        into.iter_mut()
+            .zip(statuses)
+            .map(|(buf, status)| {
+                let result = BridgeStatus::from_code(status).into_result();
+                if result.is_ok() {
+                    buf.copy_from_slice(&bytes[offset..offset + buf.len()]);
+                }
+                offset += buf.len();
+                result
+            })
+            .collect()
     }
 
-    pub fn hash(bytes: &[u8]) -> IdHash {
-        let len = bytes.len();
-        let ofs = &bytes[0];
+    fn hash(&self, bytes: &[u8]) -> IdHash {
         let mut result = IdHash::default();
-        unsafe { hash(ofs, len as i32, &mut result) };
+        unsafe { hash(bytes.as_ptr(), bytes.len() as i32, &mut result) };
         result
     }
 
-    pub fn take_bytes(id: &Id) -> Result<Vec<u8>, CanonError> {
-        // No-op in bridge version
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
         let len = id.size();
-        let mut buf = Vec::with_capacity(len);
-        buf.resize_with(len, || 0);
-        Self::get(&id.hash(), &mut buf[..])?;
+        let mut buf = vec![0u8; len];
+        let status = unsafe { take(&id.hash(), buf.as_mut_ptr(), len as i32) };
+        BridgeStatus::from_code(status).into_result()?;
         Ok(buf)
     }
+
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        BridgeStatus::from_code(unsafe { remove(hash) }).into_result()
+    }
+
+    fn refs(&self, hash: &IdHash) -> usize {
+        unsafe { refs(hash) as usize }
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        match unsafe { size(hash) } {
+            len if len < 0 => None,
+            len => Some(len as usize),
+        }
+    }
+
+    fn len(&self) -> usize {
+        unsafe { len() as usize }
+    }
+
+    fn clear(&self) {
+        unsafe { clear() }
+    }
 }
 
+// Fallible imports return a `BridgeStatus` code
+#[cfg(target_arch = "wasm32")]
 #[link(wasm_import_module = "canon")]
 extern "C" {
-    pub fn put(buf: &u8, len: i32, ret_hash: &mut IdHash);
-    pub fn get(hash: &IdHash, buf: &mut u8, len: i32);
-    pub fn hash(ofs: &u8, len: i32, buf: &mut IdHash);
+    fn abi_version() -> i32;
+    fn put(buf: *const u8, len: i32, ret_hash: &mut IdHash) -> i32;
+    fn get(hash: &IdHash, buf: *mut u8, len: i32) -> i32;
+    fn put_many(
+        algorithm: i32,
+        bufs: *const u8,
+        lens: *const i32,
+        count: i32,
+        ret_hashes: *mut IdHash,
+        ret_new: *mut u8,
+    ) -> i32;
+    fn get_many(
+        hashes: *const IdHash,
+        lens: *const i32,
+        count: i32,
+        buf: *mut u8,
+        ret_status: *mut i32,
+    );
+    fn take(hash: &IdHash, buf: *mut u8, len: i32) -> i32;
+    fn remove(hash: &IdHash) -> i32;
+    fn refs(hash: &IdHash) -> i64;
+    fn hash(buf: *const u8, len: i32, ret_hash: &mut IdHash);
+    fn size(hash: &IdHash) -> i64;
+    fn len() -> i64;
+    fn clear();
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::store::bridge_mock::{
+    abi_version, clear, get, get_many, hash, len, put, put_many, refs, remove,
+    size, take,
+};
@@ -0,0 +1,144 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! In-process fake of the `canon` import module, letting `BridgeStore` run
+//! natively. Each thread has its own host store.
+
+use core::slice;
+
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash};
+use crate::store::{BridgeStatus, HostStore, StoreBackend, BRIDGE_ABI_VERSION};
+
+thread_local! {
+    static HOST: HostStore = HostStore::default();
+}
+
+fn status<T>(result: &Result<T, crate::CanonError>) -> i32 {
+    BridgeStatus::from_result(result).code()
+}
+
+pub unsafe fn abi_version() -> i32 {
+    BRIDGE_ABI_VERSION as i32
+}
+
+pub unsafe fn put(buf: *const u8, len: i32, ret_hash: &mut IdHash) -> i32 {
+    let bytes = slice::from_raw_parts(buf, len as usize);
+    *ret_hash = HOST.with(|host| host.put(bytes));
+    BridgeStatus::Ok.code()
+}
+
+pub unsafe fn put_many(
+    algorithm: i32,
+    bufs: *const u8,
+    lens: *const i32,
+    count: i32,
+    ret_hashes: *mut IdHash,
+    ret_new: *mut u8,
+) -> i32 {
+    let algorithm = match HashAlgorithm::from_version(algorithm as u8) {
+        Some(algorithm) => algorithm,
+        None => return BridgeStatus::InvalidEncoding.code(),
+    };
+    let count = count as usize;
+    let lens = slice::from_raw_parts(lens, count);
+    let hashes = slice::from_raw_parts_mut(ret_hashes, count);
+    let new = slice::from_raw_parts_mut(ret_new, count);
+
+    let total = lens.iter().map(|len| *len as usize).sum();
+    let mut bytes = slice::from_raw_parts(bufs, total);
+
+    let mut values = Vec::with_capacity(count);
+    for len in lens {
+        let (value, rest) = bytes.split_at(*len as usize);
+        values.push(value);
+        bytes = rest;
+    }
+
+    match HOST.with(|host| host.insert_many(algorithm, &values)) {
+        Ok(inserted) => {
+            for (i, (hash, is_new)) in inserted.into_iter().enumerate() {
+                hashes[i] = hash;
+                new[i] = is_new as u8;
+            }
+            BridgeStatus::Ok.code()
+        }
+        err => status(&err),
+    }
+}
+
+pub unsafe fn get(hash: &IdHash, buf: *mut u8, len: i32) -> i32 {
+    let into = slice::from_raw_parts_mut(buf, len as usize);
+    status(&HOST.with(|host| host.get(hash, into)))
+}
+
+pub unsafe fn get_many(
+    hashes: *const IdHash,
+    lens: *const i32,
+    count: i32,
+    buf: *mut u8,
+    ret_status: *mut i32,
+) {
+    let count = count as usize;
+    let hashes = slice::from_raw_parts(hashes, count);
+    let lens = slice::from_raw_parts(lens, count);
+    let statuses = slice::from_raw_parts_mut(ret_status, count);
+
+    let total = lens.iter().map(|len| *len as usize).sum();
+    let mut bytes = slice::from_raw_parts_mut(buf, total);
+
+    HOST.with(|host| {
+        for i in 0..count {
+            let (into, rest) = bytes.split_at_mut(lens[i] as usize);
+            statuses[i] = status(&host.get(&hashes[i], into));
+            bytes = rest;
+        }
+    });
+}
+
+pub unsafe fn take(hash: &IdHash, buf: *mut u8, len: i32) -> i32 {
+    // the payload of a stored Id is its hash, whatever its version
+    let id = match Id::from_parts(0, len as u32, *hash) {
+        Some(id) => id,
+        None => return BridgeStatus::InvalidEncoding.code(),
+    };
+    match HOST.with(|host| host.take_bytes(&id)) {
+        Ok(bytes) => {
+            slice::from_raw_parts_mut(buf, len as usize)
+                .copy_from_slice(&bytes);
+            BridgeStatus::Ok.code()
+        }
+        err => status(&err),
+    }
+}
+
+pub unsafe fn remove(hash: &IdHash) -> i32 {
+    status(&HOST.with(|host| host.release(hash)))
+}
+
+pub unsafe fn refs(hash: &IdHash) -> i64 {
+    HOST.with(|host| host.refs(hash)) as i64
+}
+
+pub unsafe fn hash(buf: *const u8, len: i32, ret_hash: &mut IdHash) {
+    let bytes = slice::from_raw_parts(buf, len as usize);
+    *ret_hash = HOST.with(|host| host.hash(bytes));
+}
+
+pub unsafe fn size(hash: &IdHash) -> i64 {
+    match HOST.with(|host| host.size_of(hash)) {
+        Some(len) => len as i64,
+        None => -1,
+    }
+}
+
+pub unsafe fn len() -> i64 {
+    HOST.with(|host| host.len()) as i64
+}
+
+pub unsafe fn clear() {
+    HOST.with(|host| host.clear())
+}
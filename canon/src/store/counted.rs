@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Reference counted maps shared by the in-memory backends
+
+use std::collections::hash_map::{Entry, HashMap};
+
+use crate::canon::CanonError;
+use crate::id::{Id, IdHash};
+use crate::store::GcStats;
+
+#[derive(Debug)]
+pub(crate) struct Counted {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) refs: usize,
+}
+
+pub(crate) type CountedMap = HashMap<IdHash, Counted>;
+
+pub(crate) fn get(
+    map: &CountedMap,
+    hash: &IdHash,
+    into: &mut [u8],
+) -> Result<(), CanonError> {
+    match map.get(hash) {
+        Some(counted) if counted.bytes.len() == into.len() => {
+            into.copy_from_slice(&counted.bytes);
+            Ok(())
+        }
+        Some(_) => Err(CanonError::InvalidEncoding),
+        None => Err(CanonError::NotFound),
+    }
+}
+
+/// Adds a reference to the value, returning true if it was not stored before
+pub(crate) fn put(map: &mut CountedMap, hash: IdHash, bytes: &[u8]) -> bool {
+    match map.entry(hash) {
+        Entry::Occupied(mut entry) => {
+            entry.get_mut().refs += 1;
+            false
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(Counted {
+                bytes: Vec::from(bytes),
+                refs: 1,
+            });
+            true
+        }
+    }
+}
+
+pub(crate) fn take_bytes(
+    map: &mut CountedMap,
+    id: &Id,
+) -> Result<Vec<u8>, CanonError> {
+    match map.entry(id.hash()) {
+        Entry::Occupied(entry) if entry.get().bytes.len() != id.size() => {
+            Err(CanonError::InvalidEncoding)
+        }
+        Entry::Occupied(mut entry) => {
+            if entry.get().refs > 1 {
+                let counted = entry.get_mut();
+                counted.refs -= 1;
+                Ok(counted.bytes.clone())
+            } else {
+                Ok(entry.remove().bytes)
+            }
+        }
+        Entry::Vacant(_) => Err(CanonError::NotFound),
+    }
+}
+
+pub(crate) fn release(
+    map: &mut CountedMap,
+    hash: &IdHash,
+) -> Result<(), CanonError> {
+    match map.entry(*hash) {
+        Entry::Occupied(mut entry) => {
+            if entry.get().refs > 1 {
+                entry.get_mut().refs -= 1;
+            } else {
+                entry.remove();
+            }
+            Ok(())
+        }
+        Entry::Vacant(_) => Err(CanonError::NotFound),
+    }
+}
+
+/// Removes the entries of `map` for which `keep` returns false
+pub(crate) fn sweep<F>(map: &mut CountedMap, keep: F) -> GcStats
+where
+    F: Fn(&IdHash) -> bool,
+{
+    let mut stats = GcStats::default();
+    map.retain(|hash, counted| {
+        let keep = keep(hash);
+        if !keep {
+            stats.entries += 1;
+            stats.bytes += counted.bytes.len();
+        }
+        keep
+    });
+    stats
+}
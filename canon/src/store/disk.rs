@@ -0,0 +1,456 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash, Payload, PAYLOAD_BYTES};
+use crate::store::refs::{self, RefMap};
+use crate::store::StoreBackend;
+
+const HASH_LEN: usize = core::mem::size_of::<IdHash>();
+
+// Every record starts with a tag. Blob records continue with the hash, a
+// little endian u32 length and the data itself, tombstones only with the
+// hash. Ref records hold a u32 length and the name, followed by a flag byte
+// and, if it is set, the version byte, u32 length and payload of the id
+// the ref now points to.
+const TAG_BLOB: u8 = 0;
+const TAG_TOMBSTONE: u8 = 1;
+const TAG_REF: u8 = 2;
+
+const HEADER_LEN: usize = 1 + HASH_LEN;
+const BLOB_HEADER_LEN: usize = HEADER_LEN + 4;
+const REF_ID_LEN: usize = 1 + 4 + PAYLOAD_BYTES;
+
+#[derive(Clone, Copy, Debug)]
+struct Location {
+    offset: u64,
+    len: u32,
+}
+
+/// File backed store, persisting values across runs of the process
+///
+/// The data lives in an append-only log, the index from hashes to log
+/// offsets is rebuilt from it when the store is opened. A last record that
+/// was only partially written, for example due to a crash, is discarded,
+/// while a corrupt record followed by others fails the opening, leaving the
+/// log untouched.
+///
+/// Writing to the log does not panic on failure. The first error is kept and
+/// returned by the next `sync`, values that could not be written are not
+/// stored, and reads failing on the disk return `CanonError::Io`.
+#[derive(Debug)]
+pub struct DiskStore {
+    file: RefCell<File>,
+    index: RefCell<HashMap<IdHash, Location>>,
+    refs: RefCell<RefMap>,
+    error: RefCell<Option<io::Error>>,
+}
+
+impl DiskStore {
+    /// Opens the store log at `path`, creating it if it does not exist
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if a record other than the
+    /// last one is corrupt.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let (index, refs, valid_len) = Self::replay(&mut file)?;
+
+        if valid_len < file.metadata()?.len() {
+            file.set_len(valid_len)?;
+            file.sync_data()?;
+        }
+
+        Ok(DiskStore {
+            file: RefCell::new(file),
+            index: RefCell::new(index),
+            refs: RefCell::new(refs),
+            error: RefCell::new(None),
+        })
+    }
+
+    /// Flushes all written records to the disk
+    ///
+    /// Returns the first error hit writing to the log since the last call,
+    /// if any.
+    pub fn sync(&self) -> io::Result<()> {
+        if let Some(error) = self.error.borrow_mut().take() {
+            return Err(error);
+        }
+        self.file.borrow().sync_data()
+    }
+
+    // Replays the log, returning the index, the refs and the length of the
+    // log up to the last complete record.
+    fn replay(
+        file: &mut File,
+    ) -> io::Result<(HashMap<IdHash, Location>, RefMap, u64)> {
+        let mut index = HashMap::new();
+        let mut refs = RefMap::new();
+        let mut offset = 0u64;
+
+        let file_len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0u8; BLOB_HEADER_LEN];
+        let mut payload = Vec::new();
+
+        loop {
+            if !read_complete(&mut reader, &mut header[..1])? {
+                break;
+            }
+
+            match header[0] {
+                TAG_BLOB => {
+                    if !read_complete(&mut reader, &mut header[1..])? {
+                        break;
+                    }
+
+                    let mut hash = IdHash::default();
+                    hash.copy_from_slice(&header[1..HEADER_LEN]);
+
+                    let mut len = [0u8; 4];
+                    len.copy_from_slice(&header[HEADER_LEN..]);
+                    let len = u32::from_le_bytes(len);
+
+                    let start = offset + BLOB_HEADER_LEN as u64;
+                    if start + len as u64 > file_len {
+                        break;
+                    }
+
+                    payload.resize(len as usize, 0);
+                    if !read_complete(&mut reader, &mut payload)? {
+                        break;
+                    }
+
+                    // a torn write can also leave a garbled payload, which
+                    // no longer matches its hash
+                    if HashAlgorithm::detect(&payload, &hash).is_none() {
+                        torn(start + len as u64, file_len)?;
+                        break;
+                    }
+
+                    index.insert(hash, Location { offset: start, len });
+                    offset = start + len as u64;
+                }
+                TAG_TOMBSTONE => {
+                    if !read_complete(&mut reader, &mut header[1..HEADER_LEN])?
+                    {
+                        break;
+                    }
+                    index.remove(&header[1..HEADER_LEN]);
+                    offset += HEADER_LEN as u64;
+                }
+                TAG_REF => {
+                    let mut len = [0u8; 4];
+                    if !read_complete(&mut reader, &mut len)? {
+                        break;
+                    }
+                    let len = u32::from_le_bytes(len) as u64;
+
+                    let start = offset + 1 + 4;
+                    if start + len + 1 > file_len {
+                        break;
+                    }
+
+                    payload.resize(len as usize + 1, 0);
+                    if !read_complete(&mut reader, &mut payload)? {
+                        break;
+                    }
+
+                    let name =
+                        match std::str::from_utf8(&payload[..len as usize]) {
+                            Ok(name) => String::from(name),
+                            Err(_) => {
+                                torn(start + len + 1, file_len)?;
+                                break;
+                            }
+                        };
+
+                    let id = match payload[len as usize] {
+                        0 => None,
+                        1 => {
+                            let mut id = [0u8; REF_ID_LEN];
+                            if !read_complete(&mut reader, &mut id)? {
+                                break;
+                            }
+                            match decode_id(&id) {
+                                Some(id) => Some(id),
+                                None => {
+                                    let end =
+                                        start + len + 1 + REF_ID_LEN as u64;
+                                    torn(end, file_len)?;
+                                    break;
+                                }
+                            }
+                        }
+                        _ => {
+                            torn(start + len + 1, file_len)?;
+                            break;
+                        }
+                    };
+
+                    offset = start + len + 1;
+                    if id.is_some() {
+                        offset += REF_ID_LEN as u64;
+                    }
+                    refs::set(&mut refs, &name, id);
+                }
+                _ => return Err(corrupt()),
+            }
+        }
+
+        Ok((index, refs, offset))
+    }
+
+    // Writes a ref update to the log and applies it
+    fn write_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        assert!(name.len() <= u32::MAX as usize, "Ref name length overflow");
+
+        let mut record =
+            Vec::with_capacity(1 + 4 + name.len() + 1 + REF_ID_LEN);
+        record.push(TAG_REF);
+        record.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        record.extend_from_slice(name.as_bytes());
+        match id {
+            Some(id) => {
+                record.push(1);
+                record.push(id.version());
+                record.extend_from_slice(&(id.size() as u32).to_le_bytes());
+                record.extend_from_slice(id.payload());
+            }
+            None => record.push(0),
+        }
+
+        // a failed write is returned by `sync`
+        let _ = self.append(&record);
+        refs::set(&mut self.refs.borrow_mut(), name, id)
+    }
+
+    // Appends a record to the log, returning the offset it was written at.
+    // On failure a partially written record is cut off again and the error
+    // kept for `sync`.
+    fn append(&self, record: &[u8]) -> Option<u64> {
+        let mut file = self.file.borrow_mut();
+        let offset = match file.seek(SeekFrom::End(0)) {
+            Ok(offset) => offset,
+            Err(e) => {
+                self.fail(e);
+                return None;
+            }
+        };
+        match file.write_all(record) {
+            Ok(()) => Some(offset),
+            Err(e) => {
+                let _ = file.set_len(offset);
+                self.fail(e);
+                None
+            }
+        }
+    }
+
+    // Keeps the first error hit writing to the log
+    fn fail(&self, error: io::Error) {
+        self.error.borrow_mut().get_or_insert(error);
+    }
+
+    fn read(&self, location: Location, into: &mut [u8]) -> io::Result<()> {
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(location.offset))?;
+        file.read_exact(into)
+    }
+
+    // Stores the bytes under their hash with the given algorithm, returning
+    // whether they were not stored before. Fails if the log could not be
+    // written.
+    fn insert_hashed(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), (IdHash, CanonError)> {
+        let hash = self.hash_with(algorithm, bytes);
+
+        if self.contains(&hash) {
+            return Ok((hash, false));
+        }
+
+        assert!(bytes.len() <= u32::MAX as usize, "Payload length overflow");
+        let len = bytes.len() as u32;
+
+        let mut record = Vec::with_capacity(BLOB_HEADER_LEN + bytes.len());
+        record.push(TAG_BLOB);
+        record.extend_from_slice(&hash);
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(bytes);
+
+        let offset = match self.append(&record) {
+            Some(offset) => offset + BLOB_HEADER_LEN as u64,
+            None => return Err((hash, CanonError::Io)),
+        };
+        self.index
+            .borrow_mut()
+            .insert(hash, Location { offset, len });
+        Ok((hash, true))
+    }
+}
+
+fn decode_id(bytes: &[u8; REF_ID_LEN]) -> Option<Id> {
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&bytes[1..5]);
+    let mut payload = Payload::default();
+    payload.copy_from_slice(&bytes[5..]);
+    Id::from_parts(bytes[0], u32::from_le_bytes(len), payload)
+}
+
+// Fails if a record that could not be replayed, ending at `end`, is followed
+// by more of the log. Only the last record can be torn by a partial write,
+// one in the middle of the log is corrupt.
+fn torn(end: u64, file_len: u64) -> io::Result<()> {
+    if end < file_len {
+        return Err(corrupt());
+    }
+    Ok(())
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Corrupt disk store log")
+}
+
+// Fills `buf` completely, returning false if the end of the log was reached
+// first.
+fn read_complete<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+impl StoreBackend for DiskStore {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.insert(bytes).0
+    }
+
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        // the error is returned by `sync`
+        self.insert_hashed(HashAlgorithm::Blake2b, bytes)
+            .unwrap_or_else(|(hash, _)| (hash, false))
+    }
+
+    fn supports(&self, _algorithm: HashAlgorithm) -> bool {
+        true
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        self.insert_hashed(algorithm, bytes).map_err(|(_, e)| e)
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        let location = match self.index.borrow().get(hash) {
+            Some(location) => *location,
+            None => return Err(CanonError::NotFound),
+        };
+
+        if location.len as usize != into.len() {
+            return Err(CanonError::InvalidEncoding);
+        }
+
+        self.read(location, into).map_err(|_| CanonError::Io)
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        let hash = id.hash();
+
+        let location = match self.index.borrow().get(&hash) {
+            Some(location) => *location,
+            None => return Err(CanonError::NotFound),
+        };
+
+        if location.len as usize != id.size() {
+            return Err(CanonError::InvalidEncoding);
+        }
+
+        let mut buf = vec![0u8; id.size()];
+        self.read(location, &mut buf).map_err(|_| CanonError::Io)?;
+
+        let mut record = [0u8; HEADER_LEN];
+        record[0] = TAG_TOMBSTONE;
+        record[1..].copy_from_slice(&hash);
+
+        if self.append(&record).is_none() {
+            return Err(CanonError::Io);
+        }
+        self.index.borrow_mut().remove(&hash);
+
+        Ok(buf)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.index.borrow().contains_key(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.index
+            .borrow()
+            .get(hash)
+            .map(|location| location.len as usize)
+    }
+
+    fn len(&self) -> usize {
+        self.index.borrow().len()
+    }
+
+    fn clear(&self) {
+        if let Err(e) = self.file.borrow().set_len(0) {
+            self.fail(e);
+        }
+        self.index.borrow_mut().clear();
+        self.refs.borrow_mut().clear();
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        self.refs.borrow().get(name).copied()
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        self.write_ref(name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        let actual = self.get_ref(name);
+        if actual != current {
+            return Err(actual);
+        }
+        self.write_ref(name, new);
+        Ok(())
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        refs::list(&self.refs.borrow())
+    }
+}
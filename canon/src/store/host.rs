@@ -4,55 +4,138 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use blake2b_simd::Params;
-
 use std::cell::RefCell;
-use std::collections::HashMap;
 
 use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
 use crate::id::{Id, IdHash};
+use crate::store::counted::{self, CountedMap};
+use crate::store::refs::{self, RefMap};
+use crate::store::{walk, GcStats, StoreBackend};
 
-thread_local! {
-    pub static STATIC_MAP: RefCell<HashMap<IdHash, Vec<u8>>> =
-    RefCell::new(HashMap::new())
+/// In-memory store, used by default on each thread of a native target
+///
+/// Values are reference counted, every `put` of a value has to be matched by
+/// a `take_bytes` or `release` before it is freed.
+#[derive(Default, Debug)]
+pub struct HostStore {
+    map: RefCell<CountedMap>,
+    refs: RefCell<RefMap>,
 }
 
-pub(crate) struct HostStore;
-
 impl HostStore {
-    pub(crate) fn get(
-        hash: &IdHash,
-        into: &mut [u8],
-    ) -> Result<(), CanonError> {
-        match STATIC_MAP.with(|m| {
-            m.borrow().get(hash).map(|vec| into.copy_from_slice(&vec))
-        }) {
-            Some(()) => Ok(()),
-            None => Err(CanonError::NotFound),
-        }
-    }
-
-    pub(crate) fn put(bytes: &[u8]) -> IdHash {
+    /// Returns the number of references held to the value with the given
+    /// hash
+    pub fn refs(&self, hash: &IdHash) -> usize {
+        self.map
+            .borrow()
+            .get(hash)
+            .map_or(0, |counted| counted.refs)
+    }
+
+    // Stores the bytes under their hash with the given algorithm, returning
+    // whether they were not stored before
+    fn insert_hashed(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> (IdHash, bool) {
         debug_assert!(bytes.len() > core::mem::size_of::<IdHash>());
-        let hash = Self::hash(bytes);
-        STATIC_MAP.with(|m| m.borrow_mut().insert(hash, Vec::from(bytes)));
-        hash
+        let hash = self.hash_with(algorithm, bytes);
+        let new = counted::put(&mut self.map.borrow_mut(), hash, bytes);
+        (hash, new)
+    }
+}
+
+impl StoreBackend for HostStore {
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        counted::get(&self.map.borrow(), hash, into)
+    }
+
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.insert(bytes).0
     }
 
-    pub(crate) fn hash(bytes: &[u8]) -> IdHash {
-        let mut state = Params::new().hash_length(32).to_state();
-        state.update(bytes);
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        self.insert_hashed(HashAlgorithm::Blake2b, bytes)
+    }
+
+    fn supports(&self, _algorithm: HashAlgorithm) -> bool {
+        true
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        Ok(self.insert_hashed(algorithm, bytes))
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        counted::take_bytes(&mut self.map.borrow_mut(), id)
+    }
+
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        counted::release(&mut self.map.borrow_mut(), hash)
+    }
 
-        let mut buf = [0u8; 32];
-        buf.copy_from_slice(state.finalize().as_ref());
-        buf
+    fn refs(&self, hash: &IdHash) -> usize {
+        HostStore::refs(self, hash)
     }
 
-    pub(crate) fn take_bytes(id: &Id) -> Result<Vec<u8>, CanonError> {
-        STATIC_MAP.with(|m| match m.borrow_mut().remove(&id.hash()) {
-            Some(vec) if id.size() == vec.len() => Ok(vec),
-            Some(_) => Err(CanonError::InvalidEncoding),
-            None => Err(CanonError::NotFound),
-        })
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.map.borrow().contains_key(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.map
+            .borrow()
+            .get(hash)
+            .map(|counted| counted.bytes.len())
+    }
+
+    fn len(&self) -> usize {
+        self.map.borrow().len()
+    }
+
+    fn clear(&self) {
+        self.map.borrow_mut().clear();
+        self.refs.borrow_mut().clear();
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        self.refs.borrow().get(name).copied()
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        refs::set(&mut self.refs.borrow_mut(), name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        refs::compare_and_swap(&mut self.refs.borrow_mut(), name, current, new)
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        refs::list(&self.refs.borrow())
+    }
+
+    fn collect_garbage(&self, roots: &[Id]) -> GcStats {
+        let roots: Vec<Id> = roots
+            .iter()
+            .chain(self.refs.borrow().values())
+            .copied()
+            .collect();
+
+        let mut map = self.map.borrow_mut();
+        let marked = walk::reachable(&roots, |hash| {
+            map.get(hash).map(|counted| &counted.bytes[..])
+        });
+        counted::sweep(&mut map, |hash| marked.contains(hash))
     }
 }
@@ -6,19 +6,123 @@
 
 use cfg_if::cfg_if;
 
+use core::cell::RefCell;
 use core::fmt;
 
+use crate::hash::HashAlgorithm;
 use crate::id::{Id, IdHash};
 use crate::CanonError;
+use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::vec::Vec;
 
+mod abi;
+mod backend;
+mod scope;
+mod stats;
+mod transaction;
+pub(crate) mod walk;
+
+pub use abi::{BridgeStatus, BRIDGE_ABI_VERSION, MIN_BRIDGE_ABI_VERSION};
+pub use backend::{GcStats, StoreBackend};
+pub use scope::StoreScope;
+pub use stats::{StoreObserver, StoreStats};
+pub use transaction::Transaction;
+
+// The store state of the current thread
+struct Context {
+    // The installed backends, the last one being in use. Every `StoreScope`
+    // adds a level on top.
+    backends: Vec<Rc<dyn StoreBackend>>,
+    stats: StoreStats,
+    observers: Vec<Rc<dyn StoreObserver>>,
+    verify: bool,
+    algorithm: HashAlgorithm,
+}
+
+impl Context {
+    const fn new() -> Self {
+        Context {
+            backends: Vec::new(),
+            stats: StoreStats::new(),
+            observers: Vec::new(),
+            verify: false,
+            algorithm: HashAlgorithm::Blake2b,
+        }
+    }
+}
+
+type Slot = RefCell<Context>;
+
 cfg_if! {
     if #[cfg(target_arch = "wasm32")] {
         mod bridge;
-        use bridge::BridgeStore as Inner;
+        pub use bridge::BridgeStore;
+
+        fn default_backend() -> Rc<dyn StoreBackend> {
+            if let Err(version) = BridgeStore::check_abi() {
+                panic!(
+                    "The host implements version {} of the canon imports, \
+                     {} or later is required",
+                    version, MIN_BRIDGE_ABI_VERSION
+                );
+            }
+            Rc::new(BridgeStore)
+        }
+
+        struct WasmSlot(Slot);
+
+        // wasm modules are single threaded, so the slot is never shared
+        unsafe impl Sync for WasmSlot {}
+
+        static CONTEXT: WasmSlot = WasmSlot(RefCell::new(Context::new()));
+
+        fn with_slot<R>(f: impl FnOnce(&Slot) -> R) -> R {
+            f(&CONTEXT.0)
+        }
     } else {
+        mod archive;
+        mod counted;
+        mod disk;
         mod host;
-        use host::HostStore as Inner;
+        mod refs;
+        mod shared;
+        mod sync;
+
+        use std::io;
+        pub use disk::DiskStore;
+        pub use host::HostStore;
+        pub use shared::SharedStore;
+        pub use sync::{ChannelTransport, Message, Transport};
+
+        #[cfg(feature = "bridge-mock")]
+        mod bridge;
+        #[cfg(feature = "bridge-mock")]
+        mod bridge_mock;
+        #[cfg(feature = "bridge-mock")]
+        pub use bridge::BridgeStore;
+
+        #[cfg(not(feature = "shared-store"))]
+        fn default_backend() -> Rc<dyn StoreBackend> {
+            fresh_backend()
+        }
+
+        fn fresh_backend() -> Rc<dyn StoreBackend> {
+            Rc::new(HostStore::default())
+        }
+
+        #[cfg(feature = "shared-store")]
+        fn default_backend() -> Rc<dyn StoreBackend> {
+            Rc::new(SharedStore::global())
+        }
+
+        thread_local! {
+            static CONTEXT: Slot = const { RefCell::new(Context::new()) };
+        }
+
+        fn with_slot<R>(f: impl FnOnce(&Slot) -> R) -> R {
+            CONTEXT.with(f)
+        }
     }
 }
 
@@ -26,70 +130,743 @@ cfg_if! {
 pub struct Store;
 
 impl Store {
+    /// Install a backend for all subsequent store operations on this thread
+    ///
+    /// Inside a `StoreScope` this replaces the backend of the scope only.
+    pub fn install<B>(backend: B)
+    where
+        B: StoreBackend + 'static,
+    {
+        let backend: Rc<dyn StoreBackend> = Rc::new(backend);
+        // dropped outside of the borrow, in case a backend touches the store
+        let _replaced = with_slot(|slot| {
+            let backends = &mut slot.borrow_mut().backends;
+            match backends.last_mut() {
+                Some(top) => Some(core::mem::replace(top, backend)),
+                None => {
+                    backends.push(backend);
+                    None
+                }
+            }
+        });
+    }
+
+    /// Runs `f` with a fresh, empty store installed, restoring the previous
+    /// one afterwards.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn scope<F, R>(f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _scope = StoreScope::new();
+        f()
+    }
+
+    /// Runs `f` in a `Transaction`, committing its changes if it returns
+    /// `Ok` and rolling them back otherwise.
+    pub fn transaction<F, R, E>(f: F) -> Result<R, E>
+    where
+        F: FnOnce() -> Result<R, E>,
+    {
+        let transaction = Transaction::begin();
+        let result = f();
+        if result.is_ok() {
+            transaction.commit();
+        }
+        result
+    }
+
+    /// Returns the backend currently in use, creating the default one for
+    /// the target if none was installed.
+    fn backend() -> Rc<dyn StoreBackend> {
+        with_slot(|slot| {
+            let backends = &mut slot.borrow_mut().backends;
+            if backends.is_empty() {
+                backends.push(default_backend());
+            }
+            backends[backends.len() - 1].clone()
+        })
+    }
+
+    // Adds a level of backends, returning the depth to truncate to when
+    // leaving it.
+    fn push_backend(backend: Rc<dyn StoreBackend>) -> usize {
+        with_slot(|slot| {
+            let backends = &mut slot.borrow_mut().backends;
+            if backends.is_empty() {
+                backends.push(default_backend());
+            }
+            backends.push(backend);
+            backends.len() - 1
+        })
+    }
+
+    fn truncate_backends(depth: usize) {
+        let _dropped = with_slot(|slot| {
+            let backends = &mut slot.borrow_mut().backends;
+            let at = core::cmp::min(depth, backends.len());
+            backends.split_off(at)
+        });
+    }
+
+    /// Returns a snapshot of the operation counters of this thread
+    pub fn stats() -> StoreStats {
+        with_slot(|slot| slot.borrow().stats)
+    }
+
+    /// Resets the operation counters of this thread
+    pub fn reset_stats() {
+        with_slot(|slot| slot.borrow_mut().stats = StoreStats::default())
+    }
+
+    /// Registers an observer of the store operations on this thread
+    pub fn observe<O>(observer: O)
+    where
+        O: StoreObserver + 'static,
+    {
+        with_slot(|slot| slot.borrow_mut().observers.push(Rc::new(observer)))
+    }
+
+    /// Removes all observers registered on this thread
+    pub fn clear_observers() {
+        let _dropped =
+            with_slot(|slot| core::mem::take(&mut slot.borrow_mut().observers));
+    }
+
+    // Updates the counters and returns the observers to notify, which are
+    // called outside of the borrow of the context.
+    fn record<F>(f: F) -> Vec<Rc<dyn StoreObserver>>
+    where
+        F: FnOnce(&mut StoreStats),
+    {
+        with_slot(|slot| {
+            let mut context = slot.borrow_mut();
+            f(&mut context.stats);
+            context.observers.clone()
+        })
+    }
+
+    /// Enables or disables verifying values fetched on this thread
+    ///
+    /// When enabled, `Store::get` rehashes the fetched bytes and returns
+    /// `CanonError::HashMismatch` if they do not match the requested hash.
+    pub fn set_verify(verify: bool) {
+        with_slot(|slot| slot.borrow_mut().verify = verify)
+    }
+
+    /// Returns true if values fetched on this thread are verified
+    pub fn verifying() -> bool {
+        with_slot(|slot| slot.borrow().verify)
+    }
+
+    /// Selects the hash algorithm for values stored on this thread
+    ///
+    /// Values already stored keep the algorithm recorded in their `Id`.
+    /// Backends that do not support the algorithm store values hashed with
+    /// Blake2b instead, which their `Id` records.
+    pub fn set_hash_algorithm(algorithm: HashAlgorithm) {
+        with_slot(|slot| slot.borrow_mut().algorithm = algorithm)
+    }
+
+    /// Returns the hash algorithm used for values stored on this thread
+    pub fn hash_algorithm() -> HashAlgorithm {
+        with_slot(|slot| slot.borrow().algorithm)
+    }
+
     /// Write the byte slice into the store and return its hash
     pub fn put(bytes: &[u8]) -> IdHash {
-        Inner::put(bytes)
+        Self::put_selected(bytes).1
+    }
+
+    // Puts the bytes hashed with the selected algorithm, falling back to
+    // Blake2b, and returns the algorithm used with the hash
+    pub(crate) fn put_selected(bytes: &[u8]) -> (HashAlgorithm, IdHash) {
+        let backend = Self::backend();
+        let algorithm = Self::put_algorithm(&*backend);
+        match backend.insert_with(algorithm, bytes) {
+            Ok((hash, new)) => {
+                Self::record_put(&hash, bytes.len(), new);
+                (algorithm, hash)
+            }
+            // the backend reports failing to store the value its own way
+            Err(_) => (algorithm, backend.hash_with(algorithm, bytes)),
+        }
+    }
+
+    // Returns the selected algorithm if the backend supports it, Blake2b
+    // otherwise
+    fn put_algorithm<B: StoreBackend + ?Sized>(backend: &B) -> HashAlgorithm {
+        let algorithm = Self::hash_algorithm();
+        if backend.supports(algorithm) {
+            algorithm
+        } else {
+            HashAlgorithm::Blake2b
+        }
+    }
+
+    /// Write the byte slice into the store under its hash with the given
+    /// algorithm, and return the hash
+    ///
+    /// Fails with `CanonError::InvalidEncoding` if the backend does not
+    /// support the algorithm, and `CanonError::Io` if it could not write the
+    /// value.
+    pub fn put_with(
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<IdHash, CanonError> {
+        let (hash, new) = Self::backend().insert_with(algorithm, bytes)?;
+        Self::record_put(&hash, bytes.len(), new);
+        Ok(hash)
+    }
+
+    /// Write every byte slice into the store and return their hashes
+    ///
+    /// Backends may store the whole batch at once, which over the bridge
+    /// takes a single call to the host. The values are hashed with Blake2b
+    /// if the backend does not support the selected algorithm.
+    pub fn put_many(values: &[&[u8]]) -> Vec<IdHash> {
+        let backend = Self::backend();
+        let algorithm = Self::put_algorithm(&*backend);
+        match backend.insert_many(algorithm, values) {
+            Ok(inserted) => values
+                .iter()
+                .zip(inserted)
+                .map(|(bytes, (hash, new))| {
+                    Self::record_put(&hash, bytes.len(), new);
+                    hash
+                })
+                .collect(),
+            // the backend reports failing to store the values its own way
+            Err(_) => values
+                .iter()
+                .map(|bytes| backend.hash_with(algorithm, bytes))
+                .collect(),
+        }
+    }
+
+    fn record_put(hash: &IdHash, len: usize, new: bool) {
+        let observers = Self::record(|stats| {
+            stats.puts += 1;
+            if new {
+                stats.bytes_stored += len as u64;
+            } else {
+                stats.duplicate_puts += 1;
+                stats.bytes_deduplicated += len as u64;
+            }
+        });
+        for observer in observers {
+            observer.on_put(hash, len, !new);
+        }
     }
 
     /// Get data with the corresponding hash and write it to a buffer
     ///
-    /// Note that the buffer must be of the right length to accept the data
+    /// Returns `CanonError::InvalidEncoding` if the buffer is not of the
+    /// length of the stored data, see `Store::size_of`
     pub fn get(hash: &IdHash, write_to: &mut [u8]) -> Result<(), CanonError> {
-        Inner::get(hash, write_to)
+        let result = Self::fetch(&*Self::backend(), None, hash, write_to);
+        Self::record_get(hash, &result);
+        result
+    }
+
+    /// Get the data for every hash and write it to the buffer at the same
+    /// position, returning the result of each lookup
+    ///
+    /// Backends may fetch the whole batch at once, which over the bridge
+    /// takes a single call to the host. Panics if the number of buffers
+    /// differs from the number of hashes.
+    pub fn get_many(
+        hashes: &[IdHash],
+        into: &mut [&mut [u8]],
+    ) -> Vec<Result<(), CanonError>> {
+        assert_eq!(hashes.len(), into.len(), "One buffer per hash expected");
+
+        let backend = Self::backend();
+        let mut results = backend.get_many(hashes, into);
+
+        for ((hash, buf), result) in
+            hashes.iter().zip(into.iter()).zip(results.iter_mut())
+        {
+            if result.is_ok()
+                && Self::verifying()
+                && !Self::matches(&*backend, None, buf, hash)
+            {
+                *result = Err(CanonError::HashMismatch);
+            }
+            Self::record_get(hash, result);
+        }
+
+        results
+    }
+
+    /// Gets the data with the corresponding hash, checking that it is `len`
+    /// bytes long before allocating for it
+    ///
+    /// When verifying, the data is hashed with `algorithm` if known.
+    pub(crate) fn get_sized(
+        hash: &IdHash,
+        len: usize,
+        algorithm: Option<HashAlgorithm>,
+    ) -> Result<Vec<u8>, CanonError> {
+        let backend = Self::backend();
+        let result = match backend.size_of(hash) {
+            Some(stored) if stored == len => {
+                let mut buf = Vec::new();
+                buf.resize_with(len, || 0);
+                Self::fetch(&*backend, algorithm, hash, &mut buf).map(|_| buf)
+            }
+            Some(_) => Err(CanonError::InvalidEncoding),
+            None => Err(CanonError::NotFound),
+        };
+
+        Self::record_get(
+            hash,
+            &result.as_ref().map(|_| ()).map_err(Clone::clone),
+        );
+        result
+    }
+
+    fn fetch(
+        backend: &dyn StoreBackend,
+        algorithm: Option<HashAlgorithm>,
+        hash: &IdHash,
+        into: &mut [u8],
+    ) -> Result<(), CanonError> {
+        backend.get(hash, into)?;
+        if Self::verifying() && !Self::matches(backend, algorithm, into, hash) {
+            return Err(CanonError::HashMismatch);
+        }
+        Ok(())
+    }
+
+    // Checks fetched bytes against their hash, computed by the backend with
+    // the given algorithm or, if unknown, with any of them
+    fn matches(
+        backend: &dyn StoreBackend,
+        algorithm: Option<HashAlgorithm>,
+        bytes: &[u8],
+        hash: &IdHash,
+    ) -> bool {
+        match algorithm {
+            Some(algorithm) => backend.hash_with(algorithm, bytes) == *hash,
+            None => Self::detect_with(backend, bytes, hash).is_some(),
+        }
+    }
+
+    fn detect_with(
+        backend: &dyn StoreBackend,
+        bytes: &[u8],
+        hash: &IdHash,
+    ) -> Option<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| backend.hash_with(*algorithm, bytes) == *hash)
+    }
+
+    // Returns the algorithm under which the backend hashes `bytes` to `hash`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn detect(bytes: &[u8], hash: &IdHash) -> Option<HashAlgorithm> {
+        Self::detect_with(&*Self::backend(), bytes, hash)
     }
 
-    /// Hash a slice of bytes
+    // Returns true if the backend stores values hashed with `algorithm`
+    pub(crate) fn supports(algorithm: HashAlgorithm) -> bool {
+        Self::backend().supports(algorithm)
+    }
+
+    fn record_get(hash: &IdHash, result: &Result<(), CanonError>) {
+        let observers = Self::record(|stats| {
+            stats.gets += 1;
+            if let Err(CanonError::NotFound) = result {
+                stats.misses += 1;
+            }
+        });
+        for observer in observers {
+            observer.on_get(hash, result);
+        }
+    }
+
+    /// Hash a slice of bytes with the algorithm used on this thread
     pub fn hash(bytes: &[u8]) -> IdHash {
-        Inner::hash(bytes)
+        Self::hash_with(Self::hash_algorithm(), bytes)
+    }
+
+    /// Hash a slice of bytes with the given algorithm
+    pub fn hash_with(algorithm: HashAlgorithm, bytes: &[u8]) -> IdHash {
+        Self::backend().hash_with(algorithm, bytes)
+    }
+
+    /// Drops a reference to the value with the given hash, freeing it once
+    /// no references remain
+    pub fn release(hash: &IdHash) -> Result<(), CanonError> {
+        Self::backend().release(hash)
+    }
+
+    /// Returns true if the store holds data for the given hash
+    pub fn contains(hash: &IdHash) -> bool {
+        Self::backend().contains(hash)
+    }
+
+    /// Returns the length of the data stored for the given hash
+    pub fn size_of(hash: &IdHash) -> Option<usize> {
+        Self::backend().size_of(hash)
+    }
+
+    /// Returns the number of values in the store
+    pub fn len() -> usize {
+        Self::backend().len()
+    }
+
+    /// Returns true if the store holds no values
+    pub fn is_empty() -> bool {
+        Self::backend().is_empty()
+    }
+
+    /// Removes every value and every ref from the store
+    pub fn clear() {
+        Self::backend().clear()
+    }
+
+    /// Returns the id the named ref points to
+    pub fn get_ref(name: &str) -> Option<Id> {
+        Self::backend().get_ref(name)
+    }
+
+    /// Points the named ref at `id`, returning the id it pointed to before
+    pub fn set_ref(name: &str, id: Id) -> Option<Id> {
+        Self::backend().set_ref(name, Some(id))
+    }
+
+    /// Deletes the named ref, returning the id it pointed to
+    pub fn delete_ref(name: &str) -> Option<Id> {
+        Self::backend().set_ref(name, None)
+    }
+
+    /// Points the named ref at `new` if it currently points at `current`,
+    /// `None` standing for a ref that does not exist
+    ///
+    /// On mismatch nothing is changed and the id the ref actually points to
+    /// is returned as the error.
+    pub fn compare_and_swap_ref(
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        Self::backend().compare_and_swap_ref(name, current, new)
+    }
+
+    /// Returns every ref with the id it points to, ordered by name
+    pub fn list_refs() -> Vec<(String, Id)> {
+        Self::backend().list_refs()
+    }
+
+    /// Removes every stored value that is not reachable from `roots` or from
+    /// one of the refs
+    ///
+    /// Nothing is freed while a `Transaction` is in progress.
+    pub fn collect_garbage(roots: &[Id]) -> GcStats {
+        Self::backend().collect_garbage(roots)
+    }
+
+    /// Writes the values reachable from `roots` as an archive
+    ///
+    /// See `Store::import` for reading them back.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export<W: io::Write>(roots: &[Id], writer: W) -> io::Result<()> {
+        archive::export(roots, &[], writer)
+    }
+
+    /// Writes every ref, and the values reachable from them, as an archive
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_refs<W: io::Write>(writer: W) -> io::Result<()> {
+        archive::export(&[], &Self::list_refs(), writer)
+    }
+
+    /// Reads an archive written by `Store::export` or `Store::export_refs`
+    /// into the store, returning its roots
+    ///
+    /// Every value is checked against its hash before being inserted. Refs
+    /// in the archive are set once all values are in.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn import<R: io::Read>(reader: R) -> io::Result<Vec<Id>> {
+        archive::import(reader)
+    }
+
+    /// Answers a request for a tree coming over `transport`, sending the
+    /// values the peer does not have, and returns how many were sent
+    ///
+    /// See `Store::fetch_tree` for the requesting side.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send_tree<T: Transport>(transport: &mut T) -> io::Result<usize> {
+        sync::send(transport)
+    }
+
+    /// Requests the tree under `root` over `transport`, storing the values
+    /// missing from this store, and returns how many were received
+    ///
+    /// Every value is checked against a hash that was asked for before being
+    /// inserted.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn fetch_tree<T: Transport>(
+        root: &Id,
+        transport: &mut T,
+    ) -> io::Result<usize> {
+        sync::fetch(root, transport)
     }
 
     pub(crate) fn take_bytes(id: &Id) -> Result<Vec<u8>, CanonError> {
-        Inner::take_bytes(id)
+        let result = Self::backend().take_bytes(id);
+
+        let observers = Self::record(|stats| stats.takes += 1);
+        if !observers.is_empty() {
+            let outcome = result.as_ref().map(|_| ()).map_err(Clone::clone);
+            for observer in observers {
+                observer.on_take(id, &outcome);
+            }
+        }
+
+        result
     }
 }
 
 /// Struct used in `Canon::encode` to read bytes from a buffer
 pub struct Sink<'a> {
-    bytes: &'a mut [u8],
-    offset: usize,
+    inner: SinkInner<'a>,
+}
+
+enum SinkInner<'a> {
+    Slice {
+        bytes: &'a mut [u8],
+        offset: usize,
+    },
+    Vec(&'a mut Vec<u8>),
+    // Buffers up to `STREAM_BUFFER` bytes before writing, keeping the
+    // first error to report it once encoding is done
+    #[cfg(not(target_arch = "wasm32"))]
+    Writer {
+        writer: &'a mut dyn io::Write,
+        buf: Vec<u8>,
+        error: Option<io::Error>,
+    },
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+const STREAM_BUFFER: usize = 4096;
+
 impl<'a> fmt::Debug for Sink<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Sink {:?}", &self.bytes[0..self.offset])
+        match &self.inner {
+            SinkInner::Slice { bytes, offset } => {
+                write!(f, "Sink {:?}", &bytes[0..*offset])
+            }
+            SinkInner::Vec(vec) => write!(f, "Sink {:?}", vec),
+            #[cfg(not(target_arch = "wasm32"))]
+            SinkInner::Writer { buf, .. } => write!(f, "Sink {:?}", buf),
+        }
     }
 }
 
 impl<'a> Sink<'a> {
     /// Creates a new sink reading from bytes
     pub fn new(bytes: &'a mut [u8]) -> Self {
-        Sink { bytes, offset: 0 }
+        Sink {
+            inner: SinkInner::Slice { bytes, offset: 0 },
+        }
+    }
+
+    /// Creates a new sink appending to a vector, growing it as needed
+    ///
+    /// Unlike with `Sink::new`, the length of the encoding does not have to
+    /// be known up front.
+    pub fn growable(vec: &'a mut Vec<u8>) -> Self {
+        Sink {
+            inner: SinkInner::Vec(vec),
+        }
+    }
+
+    // Creates a sink streaming to a writer
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn writer(writer: &'a mut dyn io::Write) -> Self {
+        Sink {
+            inner: SinkInner::Writer {
+                writer,
+                buf: Vec::with_capacity(STREAM_BUFFER),
+                error: None,
+            },
+        }
     }
 
     /// Copies bytes into the sink
     pub fn copy_bytes(&mut self, bytes: &[u8]) {
-        let len = bytes.len();
-        self.bytes[self.offset..self.offset + len].copy_from_slice(bytes);
-        self.offset += len;
+        match &mut self.inner {
+            SinkInner::Slice {
+                bytes: into,
+                offset,
+            } => {
+                let len = bytes.len();
+                into[*offset..*offset + len].copy_from_slice(bytes);
+                *offset += len;
+            }
+            SinkInner::Vec(vec) => vec.extend_from_slice(bytes),
+            #[cfg(not(target_arch = "wasm32"))]
+            SinkInner::Writer { writer, buf, error } => {
+                use std::io::Write;
+
+                if error.is_some() {
+                    return;
+                }
+                if buf.len() + bytes.len() > STREAM_BUFFER {
+                    let result = writer.write_all(buf).and_then(|_| {
+                        if bytes.len() >= STREAM_BUFFER {
+                            writer.write_all(bytes)
+                        } else {
+                            Ok(())
+                        }
+                    });
+                    buf.clear();
+                    if let Err(err) = result {
+                        *error = Some(err);
+                        return;
+                    }
+                    if bytes.len() >= STREAM_BUFFER {
+                        return;
+                    }
+                }
+                buf.extend_from_slice(bytes);
+            }
+        }
+    }
+
+    // Writes out the buffered bytes, returning the first error met
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self.inner {
+            SinkInner::Slice { .. } | SinkInner::Vec(_) => Ok(()),
+            SinkInner::Writer { writer, buf, error } => match error {
+                Some(err) => Err(err),
+                None => io::Write::write_all(writer, &buf),
+            },
+        }
     }
 }
 
 /// Struct used in `Canon::decode` to read bytes from a buffer
 pub struct Source<'a> {
-    pub(crate) bytes: &'a [u8],
-    pub(crate) offset: usize,
+    inner: SourceInner<'a>,
+    // the `Id`s decoded so far, if asked for
+    ids: Option<Vec<Id>>,
+}
+
+enum SourceInner<'a> {
+    Slice {
+        bytes: &'a [u8],
+        offset: usize,
+    },
+    // Reads exactly the bytes asked for, into a buffer as large as the
+    // largest read. The first error is kept to be reported once decoding is
+    // done.
+    #[cfg(not(target_arch = "wasm32"))]
+    Reader {
+        reader: &'a mut dyn io::Read,
+        buf: Vec<u8>,
+        error: Option<io::Error>,
+    },
 }
 
 impl<'a> Source<'a> {
     /// Creates a new source reading from bytes
     pub fn new(bytes: &'a [u8]) -> Self {
-        Source { bytes, offset: 0 }
+        Source {
+            inner: SourceInner::Slice { bytes, offset: 0 },
+            ids: None,
+        }
+    }
+
+    // Creates a source reading from bytes, keeping every `Id` decoded
+    pub(crate) fn recording(bytes: &'a [u8]) -> Self {
+        Source {
+            inner: SourceInner::Slice { bytes, offset: 0 },
+            ids: Some(Vec::new()),
+        }
+    }
+
+    // Called by `Id::decode`
+    pub(crate) fn record(&mut self, id: Id) {
+        if let Some(ids) = &mut self.ids {
+            ids.push(id);
+        }
+    }
+
+    // Returns the `Id`s decoded by a recording source, failing if any bytes
+    // were left unread
+    pub(crate) fn into_recorded(self) -> Result<Vec<Id>, CanonError> {
+        match self.inner {
+            SourceInner::Slice { bytes, offset } if offset == bytes.len() => {
+                Ok(self.ids.unwrap_or_default())
+            }
+            _ => Err(CanonError::InvalidEncoding),
+        }
+    }
+
+    // Creates a source streaming from a reader
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn reader(reader: &'a mut dyn io::Read) -> Self {
+        Source {
+            inner: SourceInner::Reader {
+                reader,
+                buf: Vec::new(),
+                error: None,
+            },
+            ids: None,
+        }
     }
 
     /// Reads the next n bytes from the source
-    pub fn read_bytes(&mut self, n: usize) -> &[u8] {
-        let old_offset = self.offset;
-        self.offset += n;
-        &self.bytes[old_offset..old_offset + n]
+    ///
+    /// Fails with `CanonError::UnexpectedEof` if fewer than n bytes are
+    /// left.
+    pub fn read_bytes(&mut self, n: usize) -> Result<&[u8], CanonError> {
+        match &mut self.inner {
+            SourceInner::Slice { bytes, offset } => {
+                let read = offset
+                    .checked_add(n)
+                    .and_then(|end| bytes.get(*offset..end))
+                    .ok_or(CanonError::UnexpectedEof)?;
+                *offset += n;
+                Ok(read)
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            SourceInner::Reader { reader, buf, error } => {
+                use std::io::Read;
+
+                if error.is_some() {
+                    return Err(CanonError::UnexpectedEof);
+                }
+
+                buf.clear();
+                // grows with the bytes actually read, not with `n`
+                match reader.take(n as u64).read_to_end(buf) {
+                    Ok(len) if len == n => Ok(&buf[..]),
+                    Ok(_) => {
+                        *error = Some(io::ErrorKind::UnexpectedEof.into());
+                        Err(CanonError::UnexpectedEof)
+                    }
+                    Err(err) => {
+                        *error = Some(err);
+                        Err(CanonError::UnexpectedEof)
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the first error met reading
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn finish(self) -> io::Result<()> {
+        match self.inner {
+            SourceInner::Slice { .. } => Ok(()),
+            SourceInner::Reader { error, .. } => error.map_or(Ok(()), Err),
+        }
     }
 }
@@ -0,0 +1,39 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::id::Id;
+
+/// Named refs, ordered by name
+pub(crate) type RefMap = BTreeMap<String, Id>;
+
+pub(crate) fn set(map: &mut RefMap, name: &str, id: Option<Id>) -> Option<Id> {
+    match id {
+        Some(id) => map.insert(String::from(name), id),
+        None => map.remove(name),
+    }
+}
+
+pub(crate) fn compare_and_swap(
+    map: &mut RefMap,
+    name: &str,
+    current: Option<Id>,
+    new: Option<Id>,
+) -> Result<(), Option<Id>> {
+    let actual = map.get(name).copied();
+    if actual != current {
+        return Err(actual);
+    }
+    set(map, name, new);
+    Ok(())
+}
+
+pub(crate) fn list(map: &RefMap) -> Vec<(String, Id)> {
+    map.iter().map(|(name, id)| (name.clone(), *id)).collect()
+}
@@ -0,0 +1,51 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::marker::PhantomData;
+
+use alloc::rc::Rc;
+
+use crate::store::{Store, StoreBackend};
+
+/// Guard installing a backend for the current thread while it is alive
+///
+/// When dropped, the backend in use before its creation is restored. Scopes
+/// can be nested, dropping an outer scope also ends all scopes within it.
+#[derive(Debug)]
+pub struct StoreScope {
+    depth: usize,
+    // the scope belongs to the thread it was created on
+    _marker: PhantomData<Rc<()>>,
+}
+
+impl StoreScope {
+    /// Creates a scope with a fresh, empty store
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        StoreScope {
+            depth: Store::push_backend(super::fresh_backend()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a scope using the given backend
+    pub fn with_backend<B>(backend: B) -> Self
+    where
+        B: StoreBackend + 'static,
+    {
+        StoreScope {
+            depth: Store::push_backend(Rc::new(backend)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for StoreScope {
+    fn drop(&mut self) {
+        Store::truncate_backends(self.depth)
+    }
+}
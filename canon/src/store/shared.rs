@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash};
+use crate::store::counted::{self, CountedMap};
+use crate::store::refs::{self, RefMap};
+use crate::store::{walk, GcStats, StoreBackend};
+
+const SHARDS: usize = 16;
+
+static GLOBAL: OnceLock<SharedStore> = OnceLock::new();
+
+/// In-memory store that can be shared between threads
+///
+/// The values are spread over a number of independently locked shards,
+/// otherwise it behaves like `HostStore`. Clones refer to the same store,
+/// install one on every thread that should see it.
+#[derive(Clone, Default, Debug)]
+pub struct SharedStore {
+    shards: Arc<[RwLock<CountedMap>; SHARDS]>,
+    refs: Arc<RwLock<RefMap>>,
+}
+
+impl SharedStore {
+    /// Returns the process-wide shared store
+    ///
+    /// With the `shared-store` feature enabled, this is the store every
+    /// thread uses by default.
+    pub fn global() -> Self {
+        GLOBAL.get_or_init(SharedStore::default).clone()
+    }
+
+    /// Returns the number of references held to the value with the given
+    /// hash
+    pub fn refs(&self, hash: &IdHash) -> usize {
+        self.read(hash).get(hash).map_or(0, |counted| counted.refs)
+    }
+
+    fn shard(&self, hash: &IdHash) -> &RwLock<CountedMap> {
+        // hashes are uniformly distributed already
+        &self.shards[hash[0] as usize % SHARDS]
+    }
+
+    // A panic while holding a lock leaves the map itself consistent, so
+    // poisoning is ignored.
+
+    fn read(&self, hash: &IdHash) -> RwLockReadGuard<'_, CountedMap> {
+        self.shard(hash)
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self, hash: &IdHash) -> RwLockWriteGuard<'_, CountedMap> {
+        self.shard(hash)
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn read_refs(&self) -> RwLockReadGuard<'_, RefMap> {
+        self.refs
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write_refs(&self) -> RwLockWriteGuard<'_, RefMap> {
+        self.refs
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Stores the bytes under their hash with the given algorithm, returning
+    // whether they were not stored before
+    fn insert_hashed(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> (IdHash, bool) {
+        debug_assert!(bytes.len() > core::mem::size_of::<IdHash>());
+        let hash = self.hash_with(algorithm, bytes);
+        let new = counted::put(&mut self.write(&hash), hash, bytes);
+        (hash, new)
+    }
+}
+
+impl StoreBackend for SharedStore {
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        counted::get(&self.read(hash), hash, into)
+    }
+
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.insert(bytes).0
+    }
+
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        self.insert_hashed(HashAlgorithm::Blake2b, bytes)
+    }
+
+    fn supports(&self, _algorithm: HashAlgorithm) -> bool {
+        true
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        Ok(self.insert_hashed(algorithm, bytes))
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        counted::take_bytes(&mut self.write(&id.hash()), id)
+    }
+
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        counted::release(&mut self.write(hash), hash)
+    }
+
+    fn refs(&self, hash: &IdHash) -> usize {
+        SharedStore::refs(self, hash)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.read(hash).contains_key(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.read(hash).get(hash).map(|counted| counted.bytes.len())
+    }
+
+    fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+            })
+            .map(|shard| shard.len())
+            .sum()
+    }
+
+    fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clear()
+        }
+        self.write_refs().clear();
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        self.read_refs().get(name).copied()
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        refs::set(&mut self.write_refs(), name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        refs::compare_and_swap(&mut self.write_refs(), name, current, new)
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        refs::list(&self.read_refs())
+    }
+
+    fn collect_garbage(&self, roots: &[Id]) -> GcStats {
+        // the refs stay locked so none can be pointed at a value being swept
+        let refs = self.read_refs();
+        let roots: Vec<Id> =
+            roots.iter().chain(refs.values()).copied().collect();
+
+        // all shards are locked for a consistent view of the store
+        let mut shards: Vec<_> = self
+            .shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .write()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+            })
+            .collect();
+
+        let marked = walk::reachable(&roots, |hash| {
+            shards[hash[0] as usize % SHARDS]
+                .get(hash)
+                .map(|counted| &counted.bytes[..])
+        });
+
+        shards.iter_mut().fold(GcStats::default(), |stats, shard| {
+            let swept = counted::sweep(shard, |hash| marked.contains(hash));
+            GcStats {
+                entries: stats.entries + swept.entries,
+                bytes: stats.bytes + swept.bytes,
+            }
+        })
+    }
+}
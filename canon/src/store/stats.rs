@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use crate::canon::CanonError;
+use crate::id::{Id, IdHash};
+
+/// Counters of the store operations performed on the current thread
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct StoreStats {
+    /// The number of values put into the store
+    pub puts: u64,
+    /// The number of puts of values that were already stored
+    pub duplicate_puts: u64,
+    /// The number of values requested from the store
+    pub gets: u64,
+    /// The number of requested values that were not found
+    pub misses: u64,
+    /// The number of values taken out of the store
+    pub takes: u64,
+    /// The number of bytes newly stored by puts
+    pub bytes_stored: u64,
+    /// The number of bytes not stored again because of duplicate puts
+    pub bytes_deduplicated: u64,
+}
+
+impl StoreStats {
+    pub(crate) const fn new() -> Self {
+        StoreStats {
+            puts: 0,
+            duplicate_puts: 0,
+            gets: 0,
+            misses: 0,
+            takes: 0,
+            bytes_stored: 0,
+            bytes_deduplicated: 0,
+        }
+    }
+}
+
+/// Hook notified of the store operations performed on the current thread
+///
+/// All methods default to doing nothing.
+pub trait StoreObserver {
+    /// Called after a value of `len` bytes was put, `duplicate` being true if
+    /// the store already held it
+    fn on_put(&self, hash: &IdHash, len: usize, duplicate: bool) {
+        let _ = (hash, len, duplicate);
+    }
+
+    /// Called after a value was requested from the store
+    fn on_get(&self, hash: &IdHash, result: &Result<(), CanonError>) {
+        let _ = (hash, result);
+    }
+
+    /// Called after the bytes of `id` were taken out of the store
+    fn on_take(&self, id: &Id, result: &Result<(), CanonError>) {
+        let _ = (id, result);
+    }
+}
@@ -0,0 +1,257 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Replication of trees between stores.
+//!
+//! ```text
+//! receiver                        sender
+//!     Want(root)       ------>
+//!                      <------    Offer(hashes)
+//!     Need(missing)    ------>
+//!                      <------    Blob(bytes) for each missing hash,
+//!                                 then Offer(children of these blobs)
+//!     ...
+//!                      <------    Done
+//! ```
+//!
+//! The sender walks the tree a level at a time, only descending into values
+//! the receiver did not have.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::canon::{Canon, CanonError};
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash, PAYLOAD_BYTES};
+use crate::store::{walk, Sink, Source, Store};
+
+/// A message of the sync protocol
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Asks for the tree under a root
+    Want(Id),
+    /// Lists hashes the sender is able to send
+    Offer(Vec<IdHash>),
+    /// Lists the offered hashes the receiver does not have
+    Need(Vec<IdHash>),
+    /// A stored value
+    Blob(Vec<u8>),
+    /// A wanted value the sender does not have
+    Missing(IdHash),
+    /// Ends the transfer
+    Done,
+}
+
+/// A bidirectional, ordered channel to a peer
+pub trait Transport {
+    /// Sends a message to the peer
+    fn send(&mut self, message: Message) -> io::Result<()>;
+
+    /// Waits for the next message from the peer
+    fn receive(&mut self) -> io::Result<Message>;
+}
+
+/// In-process transport, passing messages over channels
+#[derive(Debug)]
+pub struct ChannelTransport {
+    tx: Sender<Message>,
+    rx: Receiver<Message>,
+}
+
+impl ChannelTransport {
+    /// Returns two transports connected to each other
+    pub fn pair() -> (Self, Self) {
+        let (tx_a, rx_a) = channel();
+        let (tx_b, rx_b) = channel();
+        (
+            ChannelTransport { tx: tx_a, rx: rx_b },
+            ChannelTransport { tx: tx_b, rx: rx_a },
+        )
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn send(&mut self, message: Message) -> io::Result<()> {
+        self.tx
+            .send(message)
+            .map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn receive(&mut self) -> io::Result<Message> {
+        self.rx
+            .recv()
+            .map_err(|_| io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+fn unexpected(message: Message) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Unexpected sync message {:?}", message),
+    )
+}
+
+pub(crate) fn send<T: Transport>(transport: &mut T) -> io::Result<usize> {
+    let root = match transport.receive()? {
+        Message::Want(root) => root,
+        other => return Err(unexpected(other)),
+    };
+
+    let mut offer = Vec::new();
+    if root.size() > PAYLOAD_BYTES {
+        offer.push(*root.payload());
+    }
+
+    let mut sent = 0;
+    while !offer.is_empty() {
+        transport.send(Message::Offer(offer))?;
+
+        let need = match transport.receive()? {
+            Message::Need(need) => need,
+            other => return Err(unexpected(other)),
+        };
+
+        let mut children = BTreeSet::new();
+        for hash in need {
+            let len = match Store::size_of(&hash) {
+                Some(len) => len,
+                None => {
+                    transport.send(Message::Missing(hash))?;
+                    return Err(io::ErrorKind::NotFound.into());
+                }
+            };
+
+            let bytes = Store::get_sized(&hash, len, None)
+                .map_err(|_| io::Error::from(io::ErrorKind::NotFound))?;
+            children.extend(
+                walk::child_ids(&bytes, Store::contains)
+                    .iter()
+                    .map(|id| *id.payload()),
+            );
+
+            transport.send(Message::Blob(bytes))?;
+            sent += 1;
+        }
+
+        offer = children.into_iter().collect();
+    }
+
+    transport.send(Message::Done)?;
+    Ok(sent)
+}
+
+pub(crate) fn fetch<T: Transport>(
+    root: &Id,
+    transport: &mut T,
+) -> io::Result<usize> {
+    transport.send(Message::Want(*root))?;
+
+    let mut needed = BTreeSet::new();
+    let mut received = 0;
+
+    loop {
+        match transport.receive()? {
+            Message::Offer(offer) => {
+                let need: Vec<_> = offer
+                    .into_iter()
+                    .filter(|hash| !Store::contains(hash))
+                    .collect();
+                needed.extend(need.iter().copied());
+                transport.send(Message::Need(need))?;
+            }
+            Message::Blob(bytes) => {
+                // the value may be hashed with any of the algorithms
+                let algorithm = HashAlgorithm::ALL
+                    .iter()
+                    .copied()
+                    .find(|a| needed.remove(&Store::hash_with(*a, &bytes)));
+                let algorithm = algorithm.ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Received a value that was not asked for",
+                    )
+                })?;
+                Store::put_with(algorithm, &bytes).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Hash algorithm not supported by the store",
+                    )
+                })?;
+                received += 1;
+            }
+            Message::Missing(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Value not in the sending store",
+                ))
+            }
+            Message::Done => break,
+            other => return Err(unexpected(other)),
+        }
+    }
+
+    if root.size() > PAYLOAD_BYTES && !Store::contains(root.payload()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Transfer ended without the root value",
+        ));
+    }
+
+    Ok(received)
+}
+
+impl Canon for Message {
+    fn encode(&self, sink: &mut Sink) {
+        match self {
+            Message::Want(id) => {
+                0u8.encode(sink);
+                id.encode(sink);
+            }
+            Message::Offer(hashes) => {
+                1u8.encode(sink);
+                hashes.encode(sink);
+            }
+            Message::Need(hashes) => {
+                2u8.encode(sink);
+                hashes.encode(sink);
+            }
+            Message::Blob(bytes) => {
+                3u8.encode(sink);
+                bytes.encode(sink);
+            }
+            Message::Missing(hash) => {
+                4u8.encode(sink);
+                hash.encode(sink);
+            }
+            Message::Done => 5u8.encode(sink),
+        }
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        match u8::decode(source)? {
+            0 => Ok(Message::Want(Id::decode(source)?)),
+            1 => Ok(Message::Offer(Vec::decode(source)?)),
+            2 => Ok(Message::Need(Vec::decode(source)?)),
+            3 => Ok(Message::Blob(Vec::decode(source)?)),
+            4 => Ok(Message::Missing(IdHash::decode(source)?)),
+            5 => Ok(Message::Done),
+            _ => Err(CanonError::InvalidEncoding),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            Message::Want(id) => id.encoded_len(),
+            Message::Offer(hashes) | Message::Need(hashes) => {
+                hashes.encoded_len()
+            }
+            Message::Blob(bytes) => bytes.encoded_len(),
+            Message::Missing(hash) => hash.encoded_len(),
+            Message::Done => 0,
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::cell::{Cell, RefCell};
+use core::marker::PhantomData;
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::canon::CanonError;
+use crate::hash::HashAlgorithm;
+use crate::id::{Id, IdHash};
+use crate::store::{Store, StoreBackend};
+
+#[derive(Debug)]
+struct Pending {
+    bytes: Vec<u8>,
+    algorithm: HashAlgorithm,
+    puts: usize,
+}
+
+// Backend buffering all changes to the store below it.
+//
+// References taken or released from the base are counted, a value being
+// hidden from the transaction once every reference the base holds to it is
+// dropped. The base itself is only touched on commit.
+struct Overlay {
+    base: Rc<dyn StoreBackend>,
+    pending: RefCell<BTreeMap<IdHash, Pending>>,
+    dropped: RefCell<BTreeMap<IdHash, usize>>,
+    // refs changed in the transaction, `None` marking deleted ones
+    refs: RefCell<BTreeMap<String, Option<Id>>>,
+    cleared: Cell<bool>,
+}
+
+impl Overlay {
+    // Returns the references to a base value left to the transaction
+    fn base_refs(&self, hash: &IdHash) -> usize {
+        if self.cleared.get() {
+            return 0;
+        }
+        let dropped = self.dropped.borrow().get(hash).copied().unwrap_or(0);
+        self.base.refs(hash).saturating_sub(dropped)
+    }
+
+    fn in_base(&self, hash: &IdHash) -> bool {
+        self.base_refs(hash) > 0
+    }
+
+    // Drops a reference, from the pending values if possible, returning
+    // false if the value is not visible to the transaction.
+    fn drop_ref(&self, hash: &IdHash) -> bool {
+        let mut pending = self.pending.borrow_mut();
+        if let Some(entry) = pending.get_mut(hash) {
+            entry.puts -= 1;
+            if entry.puts == 0 {
+                pending.remove(hash);
+            }
+            true
+        } else if self.in_base(hash) {
+            *self.dropped.borrow_mut().entry(*hash).or_insert(0) += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pend(&self, hash: IdHash, algorithm: HashAlgorithm, bytes: &[u8]) {
+        self.pending
+            .borrow_mut()
+            .entry(hash)
+            .or_insert_with(|| Pending {
+                bytes: Vec::from(bytes),
+                algorithm,
+                puts: 0,
+            })
+            .puts += 1;
+    }
+
+    fn commit(&self) {
+        if self.cleared.get() {
+            self.base.clear();
+        }
+        for (hash, count) in self.dropped.borrow().iter() {
+            for _ in 0..*count {
+                // a value dropped concurrently is gone already
+                let _ = self.base.release(hash);
+            }
+        }
+        for entry in self.pending.borrow().values() {
+            for _ in 0..entry.puts {
+                if entry.algorithm == HashAlgorithm::Blake2b {
+                    self.base.insert(&entry.bytes);
+                } else {
+                    // the algorithm was checked to be supported on insert
+                    let _ =
+                        self.base.insert_with(entry.algorithm, &entry.bytes);
+                }
+            }
+        }
+        for (name, id) in self.refs.borrow().iter() {
+            self.base.set_ref(name, *id);
+        }
+    }
+}
+
+impl StoreBackend for Overlay {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.insert(bytes).0
+    }
+
+    fn insert(&self, bytes: &[u8]) -> (IdHash, bool) {
+        let hash = self.hash(bytes);
+        let new = !self.contains(&hash);
+        self.pend(hash, HashAlgorithm::Blake2b, bytes);
+        (hash, new)
+    }
+
+    fn supports(&self, algorithm: HashAlgorithm) -> bool {
+        self.base.supports(algorithm)
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        // failing now rather than on commit
+        if !self.base.supports(algorithm) {
+            return Err(CanonError::InvalidEncoding);
+        }
+
+        let hash = self.hash_with(algorithm, bytes);
+        let new = !self.contains(&hash);
+        self.pend(hash, algorithm, bytes);
+        Ok((hash, new))
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        match self.pending.borrow().get(hash) {
+            Some(entry) if entry.bytes.len() == into.len() => {
+                into.copy_from_slice(&entry.bytes);
+                Ok(())
+            }
+            Some(_) => Err(CanonError::InvalidEncoding),
+            None if self.in_base(hash) => self.base.get(hash, into),
+            None => Err(CanonError::NotFound),
+        }
+    }
+
+    fn hash(&self, bytes: &[u8]) -> IdHash {
+        self.base.hash(bytes)
+    }
+
+    fn hash_with(&self, algorithm: HashAlgorithm, bytes: &[u8]) -> IdHash {
+        self.base.hash_with(algorithm, bytes)
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        let hash = id.hash();
+
+        let mut buf = Vec::new();
+        buf.resize_with(id.size(), || 0);
+        self.get(&hash, &mut buf)?;
+
+        self.drop_ref(&hash);
+        Ok(buf)
+    }
+
+    fn release(&self, hash: &IdHash) -> Result<(), CanonError> {
+        if self.drop_ref(hash) {
+            Ok(())
+        } else {
+            Err(CanonError::NotFound)
+        }
+    }
+
+    fn refs(&self, hash: &IdHash) -> usize {
+        let puts = self.pending.borrow().get(hash).map_or(0, |e| e.puts);
+        self.base_refs(hash).saturating_add(puts)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        match self.pending.borrow().get(hash) {
+            Some(entry) => Some(entry.bytes.len()),
+            None if self.in_base(hash) => self.base.size_of(hash),
+            None => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        let pending = self.pending.borrow();
+        let added = pending.keys().filter(|hash| !self.in_base(hash)).count();
+
+        if self.cleared.get() {
+            return added;
+        }
+
+        let hidden = self
+            .dropped
+            .borrow()
+            .keys()
+            .filter(|hash| {
+                !pending.contains_key(*hash)
+                    && self.base.contains(hash)
+                    && !self.in_base(hash)
+            })
+            .count();
+
+        self.base.len() + added - hidden
+    }
+
+    fn clear(&self) {
+        self.pending.borrow_mut().clear();
+        self.dropped.borrow_mut().clear();
+        self.refs.borrow_mut().clear();
+        self.cleared.set(true);
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        match self.refs.borrow().get(name) {
+            Some(id) => *id,
+            None if self.cleared.get() => None,
+            None => self.base.get_ref(name),
+        }
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        let previous = self.get_ref(name);
+        self.refs.borrow_mut().insert(String::from(name), id);
+        previous
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        let actual = self.get_ref(name);
+        if actual != current {
+            return Err(actual);
+        }
+        self.refs.borrow_mut().insert(String::from(name), new);
+        Ok(())
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        let mut merged = BTreeMap::new();
+        if !self.cleared.get() {
+            merged.extend(
+                self.base
+                    .list_refs()
+                    .into_iter()
+                    .map(|(name, id)| (name, Some(id))),
+            );
+        }
+        merged.extend(
+            self.refs
+                .borrow()
+                .iter()
+                .map(|(name, id)| (name.clone(), *id)),
+        );
+
+        merged
+            .into_iter()
+            .filter_map(|(name, id)| id.map(|id| (name, id)))
+            .collect()
+    }
+}
+
+/// A batch of store changes that is applied all at once, or not at all
+///
+/// While the transaction is alive, values put into the store are held
+/// separately, with reads falling through to the store it was started on.
+/// Dropping the transaction without committing it rolls it back.
+///
+/// Garbage is not collected while a transaction is in progress,
+/// `Store::collect_garbage` freeing nothing until it is committed or rolled
+/// back.
+pub struct Transaction {
+    overlay: Rc<Overlay>,
+    depth: usize,
+    // the transaction belongs to the thread it was started on
+    _marker: PhantomData<Rc<()>>,
+}
+
+impl core::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Transaction {}", self.depth)
+    }
+}
+
+impl Transaction {
+    /// Starts a transaction on top of the store currently in use
+    pub fn begin() -> Self {
+        let overlay = Rc::new(Overlay {
+            base: Store::backend(),
+            pending: RefCell::new(BTreeMap::new()),
+            dropped: RefCell::new(BTreeMap::new()),
+            refs: RefCell::new(BTreeMap::new()),
+            cleared: Cell::new(false),
+        });
+
+        Transaction {
+            depth: Store::push_backend(overlay.clone()),
+            overlay,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Applies all changes made during the transaction to the underlying
+    /// store
+    pub fn commit(self) {
+        Store::truncate_backends(self.depth);
+        self.overlay.commit();
+    }
+
+    /// Discards all changes made during the transaction
+    pub fn rollback(self) {}
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        Store::truncate_backends(self.depth)
+    }
+}
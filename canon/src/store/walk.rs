@@ -0,0 +1,132 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Discovery of the `Id`s referenced by stored values.
+//!
+//! Stored bytes carry no type information, so children are found by scanning
+//! for encoded `Id`s whose hash is present in the store. Only `Id`s too large
+//! to be inlined can reference stored data, and these always encode as a
+//! version byte naming the hash algorithm, a varint length and the full 32
+//! byte hash.
+//!
+//! When the type of a value is known, `decoded_children` returns the `Id`s
+//! it decodes instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use crate::canon::{Canon, CanonError};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::hash::HashAlgorithm;
+use crate::id::Id;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::id::{IdHash, PAYLOAD_BYTES};
+use crate::store::Source;
+
+// The longest varint encoding of a u32
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_VARINT_LEN: usize = 5;
+
+/// Returns the hashed `Id`s encoded in `bytes`, in order of appearance
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn child_ids<F>(bytes: &[u8], contains: F) -> Vec<Id>
+where
+    F: Fn(&IdHash) -> bool,
+{
+    let mut children = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        match parse_id(&bytes[offset..]) {
+            Some((id, len)) if contains(id.payload()) => {
+                children.push(id);
+                offset += len;
+            }
+            _ => offset += 1,
+        }
+    }
+
+    children
+}
+
+/// Returns the `Id`s the tagged encoding of an `N` in `bytes` decodes, in
+/// order, inlined or not
+///
+/// Unlike `children`, data that merely looks like an encoded `Id` is never
+/// returned. Fails if `bytes` are not exactly such an encoding.
+pub(crate) fn decoded_children<N: Canon>(
+    bytes: &[u8],
+) -> Result<Vec<Id>, CanonError> {
+    let mut source = Source::recording(bytes);
+    Id::decode_tagged::<N>(&mut source)?;
+    source.into_recorded()
+}
+
+/// Returns the hashes of all stored values reachable from `roots`
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reachable<'a, F>(roots: &[Id], lookup: F) -> BTreeSet<IdHash>
+where
+    F: Fn(&IdHash) -> Option<&'a [u8]>,
+{
+    let mut marked = BTreeSet::new();
+    let mut stack: Vec<IdHash> = roots
+        .iter()
+        .filter(|id| id.size() > PAYLOAD_BYTES)
+        .map(|id| *id.payload())
+        .collect();
+
+    while let Some(hash) = stack.pop() {
+        if marked.contains(&hash) {
+            continue;
+        }
+
+        if let Some(bytes) = lookup(&hash) {
+            marked.insert(hash);
+            let children = child_ids(bytes, |hash| lookup(hash).is_some());
+            stack.extend(children.iter().map(|id| *id.payload()));
+        }
+    }
+
+    marked
+}
+
+// Attempts to parse a hashed `Id` at the start of `bytes`, returning it with
+// its encoded length
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_id(bytes: &[u8]) -> Option<(Id, usize)> {
+    let version = *bytes.first()?;
+    HashAlgorithm::from_version(version)?;
+
+    let mut len: u32 = 0;
+    let mut offset = 1;
+
+    loop {
+        let byte = *bytes.get(offset)?;
+        let shift = 7 * (offset - 1) as u32;
+
+        if offset > MAX_VARINT_LEN || (shift == 28 && byte > 0x0f) {
+            return None;
+        }
+
+        len |= ((byte & 0x7f) as u32) << shift;
+        offset += 1;
+
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    if (len as usize) <= PAYLOAD_BYTES {
+        return None;
+    }
+
+    let payload = bytes.get(offset..offset + PAYLOAD_BYTES)?;
+    let mut hash = IdHash::default();
+    hash.copy_from_slice(payload);
+
+    Some((Id::from_parts(version, len, hash)?, offset + PAYLOAD_BYTES))
+}
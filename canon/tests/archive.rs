@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::io;
+
+use canonical::{Canon, HostStore, Id, Repr, Store};
+
+mod common;
+use common::{leaf, leaves, node, Tree};
+
+#[test]
+fn roundtrip() {
+    let tree = node(node(leaf(0), leaf(1)), leaf(2));
+    let root = Id::new(&tree);
+    let small = Id::new(&3u8);
+
+    let mut archive = vec![];
+    Store::export(&[root, small], &mut archive).unwrap();
+
+    Store::install(HostStore::default());
+    assert!(root.reify::<Tree>().is_err());
+
+    let roots = Store::import(&archive[..]).unwrap();
+    assert_eq!(roots, vec![root, small]);
+
+    let restored: Tree = roots[0].reify().unwrap();
+    assert_eq!(leaves(&restored).unwrap(), vec![0, 1, 2]);
+    assert_eq!(roots[1].reify::<u8>().unwrap(), 3);
+}
+
+#[test]
+fn shared_values_written_once() {
+    let shared = Repr::new(leaf(0));
+    let root = Id::new(&Tree::Node(shared.clone(), shared));
+
+    let mut archive = vec![];
+    Store::export(&[root], &mut archive).unwrap();
+
+    // magic and version, the root, no refs, then the root node and the
+    // shared leaf, each with its hash and length
+    let leaf_len = leaf(0).encoded_len();
+    let expected = 8 + (4 + 37) + 4 + (36 + root.size()) + (36 + leaf_len);
+    assert_eq!(archive.len(), expected);
+}
+
+#[test]
+fn rejects_corrupted_values() {
+    let root = Id::new(&node(leaf(0), leaf(1)));
+
+    let mut archive = vec![];
+    Store::export(&[root], &mut archive).unwrap();
+
+    let last = archive.len() - 1;
+    archive[last] ^= 1;
+
+    Store::install(HostStore::default());
+    let err = Store::import(&archive[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let err = Store::import(&archive[..last]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
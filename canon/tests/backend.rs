@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use canonical::{
+    CanonError, HashAlgorithm, HostStore, Id, IdHash, Repr, Store, StoreBackend,
+};
+
+#[derive(Default)]
+struct Counting {
+    inner: HostStore,
+    puts: Cell<usize>,
+    gets: Cell<usize>,
+}
+
+impl StoreBackend for Counting {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.puts.set(self.puts.get() + 1);
+        self.inner.put(bytes)
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        self.gets.set(self.gets.get() + 1);
+        self.inner.get(hash, into)
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        self.inner.take_bytes(id)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.inner.contains(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.inner.size_of(hash)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn clear(&self) {
+        self.inner.clear()
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        self.inner.get_ref(name)
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        self.inner.set_ref(name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        self.inner.compare_and_swap_ref(name, current, new)
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        self.inner.list_refs()
+    }
+}
+
+// A minimal backend hashing with salted Blake2b
+#[derive(Default)]
+struct Salted {
+    values: RefCell<HashMap<IdHash, Vec<u8>>>,
+}
+
+impl StoreBackend for Salted {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        let hash = self.hash(bytes);
+        self.values.borrow_mut().insert(hash, bytes.to_vec());
+        hash
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        let values = self.values.borrow();
+        let bytes = values.get(hash).ok_or(CanonError::NotFound)?;
+        into.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn hash(&self, bytes: &[u8]) -> IdHash {
+        HashAlgorithm::Blake2b.hash(&[b"salt", bytes].concat())
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        let mut values = self.values.borrow_mut();
+        values.remove(&id.hash()).ok_or(CanonError::NotFound)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.values.borrow().get(hash).map(Vec::len)
+    }
+
+    fn len(&self) -> usize {
+        self.values.borrow().len()
+    }
+
+    fn clear(&self) {
+        self.values.borrow_mut().clear()
+    }
+}
+
+// Stores a limited number of values, failing to write any more
+struct Full {
+    inner: HostStore,
+    room: Cell<usize>,
+}
+
+impl Full {
+    fn new(room: usize) -> Self {
+        Full {
+            inner: HostStore::default(),
+            room: Cell::new(room),
+        }
+    }
+}
+
+impl StoreBackend for Full {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.insert(bytes).0
+    }
+
+    fn insert_with(
+        &self,
+        algorithm: HashAlgorithm,
+        bytes: &[u8],
+    ) -> Result<(IdHash, bool), CanonError> {
+        match self.room.get() {
+            0 => Err(CanonError::Io),
+            room => {
+                self.room.set(room - 1);
+                self.inner.insert_with(algorithm, bytes)
+            }
+        }
+    }
+
+    fn supports(&self, _algorithm: HashAlgorithm) -> bool {
+        true
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        self.inner.get(hash, into)
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        self.inner.take_bytes(id)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.inner.contains(hash)
+    }
+
+    fn refs(&self, hash: &IdHash) -> usize {
+        self.inner.refs(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.inner.size_of(hash)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn clear(&self) {
+        self.inner.clear()
+    }
+}
+
+#[test]
+fn custom_backend() {
+    let backend = Rc::new(Counting::default());
+    Store::install(backend.clone());
+
+    let value = [u64::MAX; 4];
+    let id = Id::new(&value);
+    assert_eq!(backend.puts.get(), 1);
+    assert!(Store::contains(&id.hash()));
+
+    assert_eq!(id.reify::<[u64; 4]>().unwrap(), value);
+    assert_eq!(backend.gets.get(), 1);
+
+    let repr = Repr::new(value);
+    let repr_id = Id::new(&repr);
+    // both the value behind the repr and the repr itself are put
+    assert_eq!(backend.puts.get(), 3);
+
+    let restored: Repr<[u64; 4]> = repr_id.reify().unwrap();
+    assert_eq!(*restored.val().unwrap(), value);
+    assert_eq!(backend.gets.get(), 3);
+}
+
+#[test]
+fn install_replaces_contents() {
+    let id = Id::new(&[u64::MAX; 4]);
+
+    Store::install(HostStore::default());
+
+    assert!(!Store::contains(&id.hash()));
+    assert!(matches!(id.reify::<[u64; 4]>(), Err(CanonError::NotFound)));
+}
+
+#[test]
+fn unsupported_algorithm() {
+    Store::install(Salted::default());
+    Store::set_hash_algorithm(HashAlgorithm::Sha256);
+
+    // stored with Blake2b instead, as the Id records
+    let value = [u64::MAX; 4];
+    let id = Id::new(&value);
+    assert_eq!(id.algorithm(), HashAlgorithm::Blake2b);
+    assert_eq!(id.reify::<[u64; 4]>().unwrap(), value);
+
+    assert!(matches!(
+        Store::put_with(HashAlgorithm::Sha256, &[1; 64]),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn verifies_with_backend_hash() {
+    Store::install(Salted::default());
+    Store::set_verify(true);
+
+    let value = [u64::MAX; 4];
+    let id = Id::new(&value);
+    assert_eq!(id.reify::<[u64; 4]>().unwrap(), value);
+
+    let hash = Store::put(&[1; 64]);
+    let mut buf = [0; 64];
+    Store::get(&hash, &mut buf).unwrap();
+}
+
+#[test]
+fn failed_puts_not_retried() {
+    let backend = Rc::new(Full::new(1));
+    Store::install(backend.clone());
+    Store::set_hash_algorithm(HashAlgorithm::Sha256);
+
+    let hashes = Store::put_many(&[&[1; 64], &[2; 64]]);
+    assert_eq!(hashes[0], HashAlgorithm::Sha256.hash(&[1; 64]));
+    assert_eq!(hashes[1], HashAlgorithm::Sha256.hash(&[2; 64]));
+
+    // the value stored before the batch failed is referenced once
+    assert_eq!(backend.refs(&hashes[0]), 1);
+    assert!(!Store::contains(&hashes[1]));
+
+    let hash = Store::put(&[3; 64]);
+    assert!(!Store::contains(&hash));
+    assert_eq!(Store::stats().duplicate_puts, 0);
+}
@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{CanonError, IdHash, Store, StoreScope};
+
+#[test]
+fn put_many_matches_put() {
+    let _scope = StoreScope::new();
+
+    let a = [1u8; 40];
+    let b = [2u8; 50];
+
+    let hashes = Store::put_many(&[&a, &b, &a]);
+
+    assert_eq!(
+        hashes,
+        vec![Store::hash(&a), Store::hash(&b), Store::hash(&a)]
+    );
+    assert_eq!(Store::len(), 2);
+
+    let stats = Store::stats();
+    assert_eq!(stats.puts, 3);
+    assert_eq!(stats.duplicate_puts, 1);
+    assert_eq!(stats.bytes_stored, 90);
+}
+
+#[test]
+fn get_many() {
+    let _scope = StoreScope::new();
+
+    let a = [1u8; 40];
+    let b = [2u8; 50];
+    let hashes = Store::put_many(&[&a, &b]);
+    let missing = IdHash::default();
+
+    let mut buf_a = [0u8; 40];
+    let mut buf_b = [0u8; 50];
+    let mut buf_missing = [0u8; 40];
+
+    let results = Store::get_many(
+        &[hashes[0], missing, hashes[1]],
+        &mut [&mut buf_a, &mut buf_missing, &mut buf_b],
+    );
+
+    assert!(matches!(
+        &results[..],
+        [Ok(()), Err(CanonError::NotFound), Ok(())]
+    ));
+    assert_eq!(buf_a, a);
+    assert_eq!(buf_b, b);
+}
+
+#[test]
+#[should_panic]
+fn get_many_needs_a_buffer_per_hash() {
+    Store::get_many(&[IdHash::default()], &mut []);
+}
@@ -0,0 +1,181 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "bridge-mock")]
+
+use canonical::{
+    BridgeStore, CanonError, HashAlgorithm, HostStore, Id, Store, StoreScope,
+    Transaction, BRIDGE_ABI_VERSION,
+};
+
+mod common;
+use common::{leaf, leaves, node, Tree};
+
+// Runs the test against a host store, then over the bridge to the fake host
+fn on_both(test: impl Fn()) {
+    {
+        let _scope = StoreScope::with_backend(HostStore::default());
+        test();
+    }
+    {
+        let _scope = StoreScope::with_backend(BridgeStore);
+        test();
+    }
+}
+
+#[test]
+fn abi_version() {
+    assert_eq!(BridgeStore::host_abi_version(), BRIDGE_ABI_VERSION);
+    assert!(BridgeStore::check_abi().is_ok());
+}
+
+#[test]
+fn tree_round_trip() {
+    on_both(|| {
+        Store::set_verify(true);
+        let id = Id::new(&node(node(leaf(0), leaf(1)), leaf(2)));
+
+        let tree: Tree = id.reify().unwrap();
+        assert_eq!(leaves(&tree).unwrap(), vec![0, 1, 2]);
+        Store::set_verify(false);
+    })
+}
+
+#[test]
+fn get_missing() {
+    on_both(|| {
+        let mut buf = [0u8; 64];
+        assert!(matches!(
+            Store::get(&[7; 32], &mut buf),
+            Err(CanonError::NotFound)
+        ));
+        assert_eq!(Store::size_of(&[7; 32]), None);
+    })
+}
+
+#[test]
+fn take_semantics() {
+    on_both(|| {
+        let value = vec![3u8; 64];
+        let id = Id::new(&value);
+        Id::new(&value);
+
+        // the value is only removed once both references are taken
+        assert_eq!(id.take_bytes().unwrap().unwrap().len(), id.size());
+        assert!(Store::contains(id.payload()));
+        assert_eq!(id.take_bytes().unwrap().unwrap().len(), id.size());
+        assert!(!Store::contains(id.payload()));
+        assert!(matches!(id.take_bytes(), Err(CanonError::NotFound)));
+
+        let bytes = [4u8; 64];
+        let hash = Store::put(&bytes);
+        Store::put(&bytes);
+
+        assert!(Store::contains(&hash));
+        Store::release(&hash).unwrap();
+        assert!(Store::contains(&hash));
+        Store::release(&hash).unwrap();
+        assert!(!Store::contains(&hash));
+        assert!(matches!(Store::release(&hash), Err(CanonError::NotFound)));
+    })
+}
+
+#[test]
+fn transaction_release() {
+    on_both(|| {
+        let bytes = [5u8; 64];
+        let hash = Store::put(&bytes);
+        Store::put(&bytes);
+
+        let transaction = Transaction::begin();
+        Store::release(&hash).unwrap();
+        assert!(Store::contains(&hash));
+        Store::release(&hash).unwrap();
+        assert!(!Store::contains(&hash));
+        transaction.commit();
+
+        assert!(!Store::contains(&hash));
+    })
+}
+
+#[test]
+fn duplicate_puts() {
+    on_both(|| {
+        let before = Store::stats();
+        let bytes = [6u8; 64];
+        Store::put(&bytes);
+        Store::put(&bytes);
+
+        let stats = Store::stats();
+        assert_eq!(stats.puts - before.puts, 2);
+        assert_eq!(stats.duplicate_puts - before.duplicate_puts, 1);
+    })
+}
+
+#[test]
+fn len_and_clear() {
+    on_both(|| {
+        Store::clear();
+        assert!(Store::is_empty());
+
+        Id::new(&[3u8; 64]);
+        Id::new(&[4u8; 64]);
+        assert_eq!(Store::len(), 2);
+
+        Store::clear();
+        assert_eq!(Store::len(), 0);
+    })
+}
+
+#[test]
+fn no_refs() {
+    let _scope = StoreScope::with_backend(BridgeStore);
+    let id = Id::new(&[3u8; 64]);
+
+    assert_eq!(Store::set_ref("head", id), None);
+    assert_eq!(Store::get_ref("head"), None);
+    assert!(Store::compare_and_swap_ref("head", None, Some(id)).is_err());
+    assert!(Store::list_refs().is_empty());
+}
+
+#[test]
+fn batches() {
+    on_both(|| {
+        let a = [1u8; 40];
+        let b = [2u8; 50];
+        let hashes = Store::put_many(&[&a, &b]);
+        assert_eq!(hashes, vec![Store::hash(&a), Store::hash(&b)]);
+
+        let mut buf_a = [0u8; 40];
+        let mut buf_b = [0u8; 50];
+        let mut buf_c = [0u8; 60];
+        let results = Store::get_many(
+            &[hashes[0], [9; 32], hashes[1]],
+            &mut [&mut buf_a, &mut buf_c, &mut buf_b],
+        );
+
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(CanonError::NotFound)));
+        assert!(results[2].is_ok());
+        assert_eq!(buf_a, a);
+        assert_eq!(buf_b, b);
+    })
+}
+
+#[test]
+fn algorithms() {
+    on_both(|| {
+        for algorithm in HashAlgorithm::ALL {
+            let bytes = [algorithm.version() + 10; 48];
+            let hash = Store::put_with(algorithm, &bytes).unwrap();
+            assert_eq!(hash, algorithm.hash(&bytes));
+
+            let mut buf = [0u8; 48];
+            Store::get(&hash, &mut buf).unwrap();
+            assert_eq!(buf, bytes);
+        }
+    })
+}
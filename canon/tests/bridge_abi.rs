@@ -0,0 +1,45 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{BridgeStatus, CanonError};
+
+#[test]
+fn status_codes() {
+    for status in [
+        BridgeStatus::Ok,
+        BridgeStatus::NotFound,
+        BridgeStatus::InvalidEncoding,
+    ] {
+        assert_eq!(BridgeStatus::from_code(status.code()), status);
+    }
+
+    assert_eq!(BridgeStatus::Ok.code(), 0);
+    assert_eq!(BridgeStatus::from_code(-1), BridgeStatus::InvalidEncoding);
+    assert_eq!(BridgeStatus::from_code(7), BridgeStatus::InvalidEncoding);
+}
+
+#[test]
+fn status_results() {
+    assert!(BridgeStatus::Ok.into_result().is_ok());
+    assert!(matches!(
+        BridgeStatus::NotFound.into_result(),
+        Err(CanonError::NotFound)
+    ));
+    assert!(matches!(
+        BridgeStatus::InvalidEncoding.into_result(),
+        Err(CanonError::InvalidEncoding)
+    ));
+
+    assert_eq!(BridgeStatus::from_result(&Ok(3)), BridgeStatus::Ok);
+    assert_eq!(
+        BridgeStatus::from_result::<()>(&Err(CanonError::NotFound)),
+        BridgeStatus::NotFound
+    );
+    assert_eq!(
+        BridgeStatus::from_result::<()>(&Err(CanonError::HashMismatch)),
+        BridgeStatus::InvalidEncoding
+    );
+}
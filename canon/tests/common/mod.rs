@@ -0,0 +1,40 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Tree fixture shared by the integration tests
+
+// each test uses only some of the helpers
+#![allow(dead_code)]
+
+use canonical::{Canon, CanonError, Repr};
+use canonical_derive::Canon;
+
+#[derive(Clone, Canon, Debug)]
+pub enum Tree {
+    Leaf([u64; 4]),
+    Node(Repr<Tree>, Repr<Tree>),
+}
+
+// Leaves are too large to be inlined
+pub fn leaf(n: u64) -> Tree {
+    Tree::Leaf([u64::MAX - n; 4])
+}
+
+pub fn node(a: Tree, b: Tree) -> Tree {
+    Tree::Node(Repr::new(a), Repr::new(b))
+}
+
+// Returns the numbers of the leaves, from left to right
+pub fn leaves(tree: &Tree) -> Result<Vec<u64>, CanonError> {
+    match tree {
+        Tree::Leaf(values) => Ok(vec![u64::MAX - values[0]]),
+        Tree::Node(a, b) => {
+            let mut leaves_a = leaves(&*a.val()?)?;
+            leaves_a.extend(leaves(&*b.val()?)?);
+            Ok(leaves_a)
+        }
+    }
+}
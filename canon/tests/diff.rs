@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    Canon, CanonError, Change, Diff, EncodeToVec, Id, Source, StoreScope,
+};
+
+mod common;
+use common::{leaf, leaves, node, Tree};
+
+fn old_tree() -> Tree {
+    node(node(leaf(0), leaf(1)), node(leaf(2), leaf(3)))
+}
+
+fn new_tree() -> Tree {
+    node(
+        node(leaf(0), leaf(1)),
+        node(leaf(2), node(leaf(4), leaf(5))),
+    )
+}
+
+fn paths(diff: &Diff) -> Vec<(&'static str, Vec<usize>)> {
+    diff.changes()
+        .iter()
+        .map(|change| match change {
+            Change::Added { path, .. } => ("added", path.clone()),
+            Change::Removed { path, .. } => ("removed", path.clone()),
+            Change::Changed { path, .. } => ("changed", path.clone()),
+        })
+        .collect()
+}
+
+#[test]
+fn identical_trees() {
+    let root = Id::new(&old_tree());
+    let diff = Diff::new::<Tree>(&root, &root).unwrap();
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn skips_unchanged_subtrees() {
+    let old = Id::new(&old_tree());
+    let new = Id::new(&new_tree());
+
+    let diff = Diff::new::<Tree>(&old, &new).unwrap();
+
+    assert_eq!(
+        paths(&diff),
+        vec![
+            ("changed", vec![]),
+            ("changed", vec![1]),
+            ("changed", vec![1, 1]),
+            ("added", vec![1, 1, 0]),
+            ("added", vec![1, 1, 1]),
+        ]
+    );
+}
+
+#[test]
+fn removed_subtrees() {
+    let old = Id::new(&node(node(leaf(0), leaf(1)), leaf(2)));
+    let new = Id::new(&node(leaf(5), leaf(2)));
+
+    let diff = Diff::new::<Tree>(&old, &new).unwrap();
+
+    assert_eq!(
+        paths(&diff),
+        vec![
+            ("changed", vec![]),
+            ("changed", vec![0]),
+            ("removed", vec![0, 0]),
+            ("removed", vec![0, 1]),
+        ]
+    );
+}
+
+#[test]
+fn apply_as_patch() {
+    let old = Id::new(&old_tree());
+    let new = Id::new(&new_tree());
+    let patch = Diff::new::<Tree>(&old, &new).unwrap().encode_to_vec();
+
+    // a store holding only the old tree
+    let _scope = StoreScope::new();
+    assert_eq!(Id::new(&old_tree()), old);
+
+    let diff = Diff::decode(&mut Source::new(&patch)).unwrap();
+    let root = diff.apply(&old).unwrap();
+
+    assert_eq!(root, new);
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1, 2, 4, 5]);
+}
+
+#[test]
+fn apply_checks_nodes() {
+    let old = Id::new(&old_tree());
+    let new = Id::new(&new_tree());
+    let mut patch = Diff::new::<Tree>(&old, &new).unwrap().encode_to_vec();
+
+    // the last byte belongs to the last added leaf
+    let last = patch.len() - 1;
+    patch[last] ^= 1;
+
+    let diff = Diff::decode(&mut Source::new(&patch)).unwrap();
+    assert!(matches!(diff.apply(&old), Err(CanonError::HashMismatch)));
+    assert!(matches!(diff.apply(&new), Err(CanonError::InvalidEncoding)));
+}
+
+#[test]
+fn ignores_ids_in_data() {
+    // plain data holding the encoded Ids of stored values
+    let data = |n| {
+        let mut data = vec![n as u8; 8];
+        data.extend(Id::new(&leaf(n)).encode_to_vec());
+        data
+    };
+    let old = Id::new(&data(1));
+    let new = Id::new(&data(2));
+
+    let diff = Diff::new::<Vec<u8>>(&old, &new).unwrap();
+    assert_eq!(paths(&diff), vec![("changed", vec![])]);
+
+    assert!(matches!(
+        Diff::new::<Tree>(&old, &new),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
@@ -0,0 +1,136 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use canonical::{
+    Canon, CanonError, DiskStore, EncodeToVec, HashAlgorithm, Id, Repr, Source,
+    Store,
+};
+
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "canon-disk-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn persists_across_opens() {
+    let path = log_path("persist");
+    let value = vec![String::from("persisted across runs"); 4];
+
+    Store::install(DiskStore::open(&path).unwrap());
+    let encoded_id = Id::new(&Repr::new(value.clone())).encode_to_vec();
+
+    // simulate a new run of the process
+    Store::install(DiskStore::open(&path).unwrap());
+
+    let id = Id::decode(&mut Source::new(&encoded_id)).unwrap();
+    let repr: Repr<Vec<String>> = id.reify().unwrap();
+    assert_eq!(*repr.val().unwrap(), value);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn take_bytes_survives_reopen() {
+    let path = log_path("take");
+
+    Store::install(DiskStore::open(&path).unwrap());
+    let id = Id::new(&[u64::MAX; 4]);
+    assert!(id.take_bytes().unwrap().is_some());
+
+    Store::install(DiskStore::open(&path).unwrap());
+    assert!(!Store::contains(&id.hash()));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn discards_partial_trailing_write() {
+    let path = log_path("partial");
+
+    Store::install(DiskStore::open(&path).unwrap());
+    let id = Id::new(&[u64::MAX; 4]);
+    let complete_len = std::fs::metadata(&path).unwrap().len();
+
+    // a blob record header claiming more bytes than were written
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[0u8; 33]).unwrap();
+    file.write_all(&1024u32.to_le_bytes()).unwrap();
+    file.write_all(&[1, 2, 3]).unwrap();
+    drop(file);
+
+    Store::install(DiskStore::open(&path).unwrap());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), complete_len);
+    assert_eq!(id.reify::<[u64; 4]>().unwrap(), [u64::MAX; 4]);
+
+    let other = Id::new(&[u64::MAX - 1; 4]);
+    Store::install(DiskStore::open(&path).unwrap());
+    assert_eq!(other.reify::<[u64; 4]>().unwrap(), [u64::MAX - 1; 4]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_corrupt_middle_record() {
+    let path = log_path("corrupt");
+
+    Store::install(DiskStore::open(&path).unwrap());
+    let values = [[1u8; 64], [2u8; 64], [3u8; 64]];
+    let hashes: Vec<_> = values.iter().map(|v| Store::put(v)).collect();
+    let len = std::fs::metadata(&path).unwrap().len();
+
+    // flip a payload byte of the second blob record
+    let record_len = 1 + 32 + 4 + 64;
+    let offset = SeekFrom::Start(record_len + 1 + 32 + 4 + 10);
+    let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.seek(offset).unwrap();
+    file.write_all(&[0xff]).unwrap();
+
+    let error = DiskStore::open(&path).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+
+    // the records written after it survive
+    file.seek(offset).unwrap();
+    file.write_all(&[2]).unwrap();
+    drop(file);
+
+    Store::install(DiskStore::open(&path).unwrap());
+    for hash in &hashes {
+        assert!(Store::contains(hash));
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn write_errors_returned_by_sync() {
+    let path = Path::new("/dev/full");
+    if !path.exists() {
+        return;
+    }
+
+    let store = Rc::new(DiskStore::open(path).unwrap());
+    Store::install(store.clone());
+
+    let hash = Store::put(&[1, 2, 3]);
+    assert!(!Store::contains(&hash));
+    assert!(matches!(
+        Store::put_with(HashAlgorithm::Blake2b, &[4, 5, 6]),
+        Err(CanonError::Io)
+    ));
+
+    assert!(store.sync().is_err());
+}
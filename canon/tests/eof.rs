@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::fmt::Debug;
+
+use canonical::{Canon, CanonError, EncodeToVec, Id, Repr, Source, StoreScope};
+
+// Decoding any strict prefix of the encoding fails without panicking
+fn truncated<T: Canon + Debug>(value: T) {
+    let bytes = value.encode_to_vec();
+
+    for len in 0..bytes.len() {
+        let result = T::decode(&mut Source::new(&bytes[..len]));
+        assert!(
+            matches!(result, Err(CanonError::UnexpectedEof)),
+            "{:?} decoded from {} of {} bytes",
+            result,
+            len,
+            bytes.len()
+        );
+    }
+
+    assert!(T::decode(&mut Source::new(&bytes)).is_ok());
+}
+
+#[test]
+fn built_in_types() {
+    truncated(7u8);
+    truncated(u16::MAX);
+    truncated(u32::MAX);
+    truncated(u64::MAX);
+    truncated(i64::MIN);
+    truncated(true);
+    truncated(Some(u64::MAX));
+    truncated(Ok::<_, u32>(String::from("truncated")));
+    truncated(Err::<u8, _>(vec![u64::MAX; 3]));
+    truncated([u32::MAX; 4]);
+    truncated((7u8, u64::MAX, String::from("tuple")));
+}
+
+#[test]
+fn ids() {
+    let _scope = StoreScope::new();
+
+    truncated(Id::new(&[u64::MAX; 8]));
+    truncated(Id::new(&7u8));
+    truncated(Repr::new(vec![u64::MAX; 8]));
+}
+
+#[test]
+fn read_bytes() {
+    let bytes = [1, 2, 3];
+    let mut source = Source::new(&bytes);
+
+    assert_eq!(source.read_bytes(2).unwrap(), &[1, 2]);
+    assert!(matches!(
+        source.read_bytes(2),
+        Err(CanonError::UnexpectedEof)
+    ));
+    // a failed read consumes nothing
+    assert_eq!(source.read_bytes(1).unwrap(), &[3]);
+    assert!(matches!(
+        source.read_bytes(usize::MAX),
+        Err(CanonError::UnexpectedEof)
+    ));
+}
+
+#[test]
+fn error_encoding() {
+    let bytes = CanonError::UnexpectedEof.encode_to_vec();
+    assert!(matches!(
+        CanonError::decode(&mut Source::new(&bytes)),
+        Ok(CanonError::UnexpectedEof)
+    ));
+}
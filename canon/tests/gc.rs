@@ -0,0 +1,46 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{Canon, CanonError, GcStats, HostStore, Id, Repr, Store};
+
+mod common;
+use common::{leaf, leaves, node, Tree};
+
+#[test]
+fn collects_unreachable() {
+    Store::install(HostStore::default());
+
+    let shared = Repr::new(leaf(0));
+
+    let a = Tree::Node(shared.clone(), Repr::new(leaf(1)));
+    let b = Tree::Node(shared, Repr::new(leaf(2)));
+
+    let id_a = Id::new(&a);
+    let id_b = Id::new(&b);
+
+    let stats = Store::collect_garbage(&[id_a]);
+
+    // the root and the unshared leaf of `b`
+    assert_eq!(stats.entries, 2);
+    let leaf_len = leaf(2).encoded_len();
+    assert_eq!(stats.bytes, id_b.size() + leaf_len);
+
+    let a: Tree = id_a.reify().unwrap();
+    assert_eq!(leaves(&a).unwrap(), vec![0, 1]);
+
+    assert!(matches!(id_b.reify::<Tree>(), Err(CanonError::NotFound)));
+}
+
+#[test]
+fn collects_everything_without_roots() {
+    Store::install(HostStore::default());
+
+    let id = Id::new(&node(leaf(0), leaf(1)));
+
+    assert_eq!(Store::collect_garbage(&[id]), GcStats::default());
+    assert_eq!(Store::collect_garbage(&[]).entries, 3);
+    assert_eq!(Store::collect_garbage(&[]), GcStats::default());
+}
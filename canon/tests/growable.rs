@@ -0,0 +1,84 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cell::Cell;
+
+use canonical::{
+    Canon, CanonError, EncodeToVec, Id, Repr, Sink, Source, StoreScope,
+};
+
+thread_local! {
+    static LENGTHS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Counts the calls to `encoded_len`
+#[derive(Clone, Debug, PartialEq)]
+struct Counted([u64; 4]);
+
+impl Canon for Counted {
+    fn encode(&self, sink: &mut Sink) {
+        self.0.encode(sink)
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        Ok(Counted(<[u64; 4]>::decode(source)?))
+    }
+
+    fn encoded_len(&self) -> usize {
+        LENGTHS.with(|lengths| lengths.set(lengths.get() + 1));
+        self.0.encoded_len()
+    }
+}
+
+fn lengths() -> usize {
+    LENGTHS.with(Cell::get)
+}
+
+#[test]
+fn appends_to_vec() {
+    let mut vec = vec![1, 2, 3];
+    let mut sink = Sink::growable(&mut vec);
+    300u16.encode(&mut sink);
+    String::from("abc").encode(&mut sink);
+
+    let mut expected = vec![1, 2, 3];
+    expected.extend(300u16.encode_to_vec());
+    expected.extend(String::from("abc").encode_to_vec());
+    assert_eq!(vec, expected);
+}
+
+#[test]
+fn matches_fixed_sink() {
+    let value = (vec![u64::MAX; 100], String::from("growable"), Some(7u32));
+
+    let mut fixed = vec![0; value.encoded_len()];
+    value.encode(&mut Sink::new(&mut fixed));
+
+    assert_eq!(value.encode_to_vec(), fixed);
+}
+
+#[test]
+fn single_pass() {
+    let _scope = StoreScope::new();
+    let before = lengths();
+
+    let value = Counted([u64::MAX; 4]);
+    let bytes = value.encode_to_vec();
+    assert_eq!(lengths(), before);
+
+    let pair = (Repr::new(value.clone()), Repr::new(Counted([1; 4])));
+    let encoded = pair.encode_to_vec();
+    let id = Id::new(&value);
+    assert_eq!(lengths(), before);
+
+    assert_eq!(id.reify::<Counted>().unwrap(), value);
+    assert_eq!(Counted::decode(&mut Source::new(&bytes)).unwrap(), value);
+
+    let decoded =
+        <(Repr<Counted>, Repr<Counted>)>::decode(&mut Source::new(&encoded))
+            .unwrap();
+    assert_eq!(*decoded.1.val().unwrap(), Counted([1; 4]));
+}
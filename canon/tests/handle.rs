@@ -4,7 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-#![allow(clippy::unit_cmp)]
+#![allow(clippy::unit_cmp, clippy::let_unit_value)]
 
 use canonical::{Id, Repr};
 
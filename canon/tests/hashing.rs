@@ -0,0 +1,142 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    Canon, CanonError, DiskStore, EncodeToVec, HashAlgorithm, HostStore, Id,
+    Repr, Source, Store, StoreScope, Transaction,
+};
+
+mod common;
+use common::{leaf, leaves, Tree};
+
+// Builds a tree with each node hashed with a different algorithm
+fn mixed_tree() -> Id {
+    Store::set_hash_algorithm(HashAlgorithm::Sha256);
+    let a = Repr::new(leaf(0));
+    // encoding stores the value and fixes its id
+    a.encode_to_vec();
+
+    Store::set_hash_algorithm(HashAlgorithm::Blake3);
+    let b = Repr::new(leaf(1));
+    b.encode_to_vec();
+
+    Store::set_hash_algorithm(HashAlgorithm::Blake2b);
+    Id::new(&Tree::Node(a, b))
+}
+
+#[test]
+fn algorithm_in_version() {
+    let _scope = StoreScope::new();
+
+    for algorithm in HashAlgorithm::ALL {
+        Store::set_hash_algorithm(algorithm);
+
+        let value = leaf(0);
+        let id = Id::new(&value);
+
+        assert_eq!(id.algorithm(), algorithm);
+        assert_eq!(id.hash(), algorithm.hash(&value.encode_to_vec()));
+        assert_eq!(id.encode_to_vec()[0], algorithm.version());
+
+        let inlined = Id::new(&7u8);
+        assert_eq!(inlined.algorithm(), algorithm);
+        assert_eq!(inlined.hash(), algorithm.hash(&[7]));
+    }
+}
+
+#[test]
+fn algorithms_differ() {
+    let bytes = [1u8; 64];
+    let hashes: Vec<_> =
+        HashAlgorithm::ALL.iter().map(|a| a.hash(&bytes)).collect();
+
+    assert_ne!(hashes[0], hashes[1]);
+    assert_ne!(hashes[1], hashes[2]);
+    assert_ne!(hashes[0], hashes[2]);
+
+    for (algorithm, hash) in HashAlgorithm::ALL.iter().zip(&hashes) {
+        assert_eq!(HashAlgorithm::detect(&bytes, hash), Some(*algorithm));
+    }
+}
+
+#[test]
+fn decode_versions() {
+    let _scope = StoreScope::new();
+
+    Store::set_hash_algorithm(HashAlgorithm::Blake3);
+    let id = Id::new(&leaf(0));
+
+    let mut bytes = id.encode_to_vec();
+    assert_eq!(Id::decode(&mut Source::new(&bytes)).unwrap(), id);
+
+    bytes[0] = 3;
+    assert!(matches!(
+        Id::decode(&mut Source::new(&bytes)),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn mixed_store_readable() {
+    let _scope = StoreScope::new();
+    Store::set_verify(true);
+
+    let root = mixed_tree();
+
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1]);
+}
+
+#[test]
+fn mixed_archive() {
+    let _scope = StoreScope::new();
+    let root = mixed_tree();
+
+    let mut archive = vec![];
+    Store::export(&[root], &mut archive).unwrap();
+
+    let _scope = StoreScope::with_backend(HostStore::default());
+    Store::import(&archive[..]).unwrap();
+
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1]);
+}
+
+#[test]
+fn mixed_disk_store() {
+    let path = std::env::temp_dir()
+        .join(format!("canon-disk-hashing-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let root = {
+        let _scope = StoreScope::with_backend(DiskStore::open(&path).unwrap());
+        mixed_tree()
+    };
+
+    let _scope = StoreScope::with_backend(DiskStore::open(&path).unwrap());
+    assert_eq!(Store::len(), 3);
+
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn transaction_keeps_algorithm() {
+    let _scope = StoreScope::new();
+    Store::set_hash_algorithm(HashAlgorithm::Sha256);
+
+    let transaction = Transaction::begin();
+    let id = Id::new(&leaf(0));
+    transaction.commit();
+
+    Store::set_verify(true);
+    assert_eq!(
+        id.reify::<Tree>().unwrap().encode_to_vec(),
+        leaf(0).encode_to_vec()
+    );
+}
@@ -4,6 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+#![allow(clippy::drop_non_drop)]
+
 use canonical::{Canon, Sink, Source};
 
 #[test]
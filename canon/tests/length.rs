@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    Canon, CanonError, EncodeToVec, Id, Source, Store, StoreScope,
+};
+
+const VALUE: [u64; 4] = [u64::MAX; 4];
+
+#[test]
+fn size_queries() {
+    let _scope = StoreScope::new();
+    let id = Id::new(&VALUE);
+
+    assert!(Store::contains(&id.hash()));
+    assert_eq!(Store::size_of(&id.hash()), Some(id.size()));
+    assert_eq!(Store::size_of(&[0u8; 32]), None);
+}
+
+#[test]
+fn mismatched_buffer() {
+    let _scope = StoreScope::new();
+    let id = Id::new(&VALUE);
+
+    let mut short = vec![0u8; id.size() - 1];
+    assert!(matches!(
+        Store::get(&id.hash(), &mut short),
+        Err(CanonError::InvalidEncoding)
+    ));
+
+    let mut long = vec![0u8; id.size() + 1];
+    assert!(matches!(
+        Store::get(&id.hash(), &mut long),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn forged_length() {
+    let _scope = StoreScope::new();
+    let mut bytes = Id::new(&VALUE).encode_to_vec();
+
+    // the length varint directly follows the version byte
+    assert_eq!(bytes[1], 40);
+    bytes[1] = 41;
+
+    let forged = Id::decode(&mut Source::new(&bytes)).unwrap();
+    assert!(matches!(
+        forged.reify::<[u64; 4]>(),
+        Err(CanonError::InvalidEncoding)
+    ));
+
+    // a length far beyond anything stored is rejected before allocating
+    let mut huge = vec![0u8, 0xff, 0xff, 0xff, 0xff, 0x0f];
+    huge.extend_from_slice(&bytes[2..]);
+    let forged = Id::decode(&mut Source::new(&huge)).unwrap();
+    assert!(matches!(
+        forged.reify::<[u64; 4]>(),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
@@ -0,0 +1,106 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    Canon, CanonError, EncodeToVec, HostStore, Id, Proof, Source, StoreScope,
+};
+
+mod common;
+use common::{leaf, node, Tree};
+
+fn tree() -> Tree {
+    node(node(leaf(0), leaf(1)), node(leaf(2), leaf(3)))
+}
+
+#[test]
+fn proves_inclusion() {
+    let root = Id::new(&tree());
+
+    for (path, n) in [([0, 0], 0), ([0, 1], 1), ([1, 0], 2), ([1, 1], 3)] {
+        let proof = Proof::new::<Tree>(&root, &path).unwrap();
+        assert_eq!(proof.depth(), 2);
+
+        assert!(proof.verify::<Tree, _>(&root, &leaf(n)));
+        assert!(!proof.verify::<Tree, _>(&root, &leaf(n + 1)));
+    }
+}
+
+#[test]
+fn verifies_without_store() {
+    let root = Id::new(&tree());
+    let proof = Proof::new::<Tree>(&root, &[1, 0]).unwrap();
+
+    // the proof is shipped encoded and checked against an empty store
+    let encoded = proof.encode_to_vec();
+    let _scope = StoreScope::with_backend(HostStore::default());
+
+    let decoded = Proof::decode(&mut Source::new(&encoded)).unwrap();
+    assert_eq!(decoded, proof);
+    assert!(decoded.verify::<Tree, _>(&root, &leaf(2)));
+}
+
+#[test]
+fn rejects_other_roots() {
+    let root = Id::new(&tree());
+    let other = Id::new(&node(leaf(4), leaf(5)));
+
+    let proof = Proof::new::<Tree>(&root, &[0, 1]).unwrap();
+    assert!(!proof.verify::<Tree, _>(&other, &leaf(1)));
+}
+
+#[test]
+fn empty_path_proves_root() {
+    let tree = node(leaf(0), leaf(1));
+    let root = Id::new(&tree);
+
+    let proof = Proof::new::<Tree>(&root, &[]).unwrap();
+    assert!(proof.verify::<Tree, _>(&root, &tree));
+}
+
+#[test]
+fn missing_child() {
+    let root = Id::new(&tree());
+
+    assert!(matches!(
+        Proof::new::<Tree>(&root, &[2]),
+        Err(CanonError::NotFound)
+    ));
+    assert!(matches!(
+        Proof::new::<Tree>(&root, &[0, 0, 0]),
+        Err(CanonError::NotFound)
+    ));
+}
+
+#[test]
+fn proves_inlined_values() {
+    let small = Tree::Leaf([1, 2, 3, 4]);
+    let root = Id::new(&node(small.clone(), leaf(0)));
+
+    let proof = Proof::new::<Tree>(&root, &[0]).unwrap();
+    assert!(proof.verify::<Tree, _>(&root, &small));
+
+    let proof = Proof::new::<Tree>(&root, &[1]).unwrap();
+    assert!(proof.verify::<Tree, _>(&root, &leaf(0)));
+}
+
+#[test]
+fn rejects_ids_in_data() {
+    let value = leaf(7);
+
+    // plain data holding the encoded Id of a value that was never stored
+    let data = {
+        let _scope = StoreScope::with_backend(HostStore::default());
+        Id::new(&value).encode_to_vec()
+    };
+    let root = Id::new(&data);
+
+    // a single level pointing at the first Id in the root
+    let forged = vec![(data.encode_to_vec(), 0u32)].encode_to_vec();
+    let proof = Proof::decode(&mut Source::new(&forged)).unwrap();
+
+    assert!(!proof.verify::<Vec<u8>, _>(&root, &value));
+    assert!(!proof.verify::<Tree, _>(&root, &value));
+}
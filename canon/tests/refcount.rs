@@ -0,0 +1,52 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::rc::Rc;
+
+use canonical::{CanonError, HostStore, Id, Repr, Store};
+
+const VALUE: [u64; 4] = [u64::MAX; 4];
+
+#[test]
+fn take_bytes_keeps_shared_values() {
+    let store = Rc::new(HostStore::default());
+    Store::install(store.clone());
+
+    let id = Id::new(&VALUE);
+    // a second tree referencing the same value
+    let tree = (Repr::new(VALUE), 1u8);
+    let tree_id = Id::new(&tree);
+    assert_eq!(store.refs(&id.hash()), 2);
+
+    assert!(id.take_bytes().unwrap().is_some());
+    assert_eq!(store.refs(&id.hash()), 1);
+
+    let tree: (Repr<[u64; 4]>, u8) = tree_id.reify().unwrap();
+    assert_eq!(*tree.0.val().unwrap(), VALUE);
+
+    assert!(id.take_bytes().unwrap().is_some());
+    assert!(matches!(id.take_bytes(), Err(CanonError::NotFound)));
+}
+
+#[test]
+fn release() {
+    let store = Rc::new(HostStore::default());
+    Store::install(store.clone());
+
+    let a = Id::new(&VALUE);
+    let b = Id::new(&VALUE);
+    assert_eq!(a, b);
+
+    a.release().unwrap();
+    assert_eq!(b.reify::<[u64; 4]>().unwrap(), VALUE);
+
+    b.release().unwrap();
+    assert!(!Store::contains(&b.hash()));
+    assert!(matches!(b.release(), Err(CanonError::NotFound)));
+
+    // inlined ids hold no references
+    Id::new(&1u8).release().unwrap();
+}
@@ -0,0 +1,154 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{DiskStore, HostStore, Id, Store, StoreScope, Transaction};
+
+fn value(n: u64) -> [u64; 8] {
+    [u64::MAX - n; 8]
+}
+
+#[test]
+fn set_get_delete() {
+    let _scope = StoreScope::new();
+
+    let a = Id::new(&value(1));
+    let b = Id::new(&value(2));
+
+    assert_eq!(Store::get_ref("head"), None);
+    assert_eq!(Store::set_ref("head", a), None);
+    assert_eq!(Store::set_ref("head", b), Some(a));
+    assert_eq!(Store::set_ref("tail", a), None);
+
+    assert_eq!(Store::get_ref("head"), Some(b));
+    assert_eq!(
+        Store::list_refs(),
+        vec![(String::from("head"), b), (String::from("tail"), a)]
+    );
+
+    assert_eq!(Store::delete_ref("head"), Some(b));
+    assert_eq!(Store::delete_ref("head"), None);
+    assert_eq!(Store::list_refs(), vec![(String::from("tail"), a)]);
+}
+
+#[test]
+fn compare_and_swap() {
+    let _scope = StoreScope::new();
+
+    let a = Id::new(&value(1));
+    let b = Id::new(&value(2));
+
+    assert_eq!(
+        Store::compare_and_swap_ref("head", Some(a), Some(b)),
+        Err(None)
+    );
+    assert_eq!(Store::compare_and_swap_ref("head", None, Some(a)), Ok(()));
+    assert_eq!(
+        Store::compare_and_swap_ref("head", None, Some(b)),
+        Err(Some(a))
+    );
+    assert_eq!(Store::get_ref("head"), Some(a));
+
+    assert_eq!(
+        Store::compare_and_swap_ref("head", Some(a), Some(b)),
+        Ok(())
+    );
+    assert_eq!(Store::compare_and_swap_ref("head", Some(b), None), Ok(()));
+    assert_eq!(Store::get_ref("head"), None);
+}
+
+#[test]
+fn refs_are_gc_roots() {
+    let _scope = StoreScope::new();
+
+    let kept = Id::new(&value(1));
+    let dropped = Id::new(&value(2));
+    Store::set_ref("kept", kept);
+
+    let stats = Store::collect_garbage(&[]);
+
+    assert_eq!(stats.entries, 1);
+    assert_eq!(kept.reify::<[u64; 8]>().unwrap(), value(1));
+    assert!(!Store::contains(&dropped.hash()));
+}
+
+#[test]
+fn clear_removes_refs() {
+    let _scope = StoreScope::new();
+
+    Store::set_ref("head", Id::new(&value(1)));
+    Store::clear();
+
+    assert!(Store::list_refs().is_empty());
+}
+
+#[test]
+fn export_refs_roundtrip() {
+    let mut archive = vec![];
+    let a = Id::new(&value(1));
+
+    {
+        let _scope = StoreScope::new();
+        let a = Id::new(&value(1));
+        Store::set_ref("head", a);
+        Store::export_refs(&mut archive).unwrap();
+    }
+
+    let _scope = StoreScope::with_backend(HostStore::default());
+    let roots = Store::import(&archive[..]).unwrap();
+
+    assert!(roots.is_empty());
+    assert_eq!(Store::get_ref("head"), Some(a));
+    assert_eq!(a.reify::<[u64; 8]>().unwrap(), value(1));
+}
+
+#[test]
+fn transaction_refs() {
+    let _scope = StoreScope::new();
+
+    let a = Id::new(&value(1));
+    let b = Id::new(&value(2));
+    Store::set_ref("head", a);
+
+    let transaction = Transaction::begin();
+    Store::set_ref("head", b);
+    Store::set_ref("tail", a);
+    assert_eq!(Store::get_ref("head"), Some(b));
+    transaction.rollback();
+
+    assert_eq!(Store::list_refs(), vec![(String::from("head"), a)]);
+
+    let transaction = Transaction::begin();
+    Store::delete_ref("head");
+    Store::set_ref("tail", b);
+    assert_eq!(Store::list_refs(), vec![(String::from("tail"), b)]);
+    transaction.commit();
+
+    assert_eq!(Store::list_refs(), vec![(String::from("tail"), b)]);
+}
+
+#[test]
+fn disk_refs_persist() {
+    let path = std::env::temp_dir()
+        .join(format!("canon-disk-refs-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let a = Id::new(&value(1));
+    let b = Id::new(&value(2));
+
+    {
+        let _scope = StoreScope::with_backend(DiskStore::open(&path).unwrap());
+        Id::new(&value(1));
+        Store::set_ref("head", a);
+        Store::set_ref("tail", b);
+        Store::delete_ref("tail");
+    }
+
+    let _scope = StoreScope::with_backend(DiskStore::open(&path).unwrap());
+    assert_eq!(Store::list_refs(), vec![(String::from("head"), a)]);
+    assert_eq!(a.reify::<[u64; 8]>().unwrap(), value(1));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,52 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{CanonError, HostStore, Id, Store, StoreScope};
+
+#[test]
+fn scoped_closure() {
+    let outer = Id::new(&[u64::MAX; 4]);
+
+    let inner = Store::scope(|| {
+        assert!(Store::is_empty());
+        assert!(outer.reify::<[u64; 4]>().is_err());
+
+        let inner = Id::new(&[u64::MAX - 1; 4]);
+        assert_eq!(Store::len(), 1);
+        inner
+    });
+
+    assert_eq!(outer.reify::<[u64; 4]>().unwrap(), [u64::MAX; 4]);
+    assert!(matches!(
+        inner.reify::<[u64; 4]>(),
+        Err(CanonError::NotFound)
+    ));
+}
+
+#[test]
+fn nested_guards() {
+    let outer = StoreScope::new();
+    Id::new(&[u64::MAX; 4]);
+
+    {
+        let _inner = StoreScope::with_backend(HostStore::default());
+        assert_eq!(Store::len(), 0);
+
+        Id::new(&[u64::MAX - 1; 4]);
+        Id::new(&[u64::MAX - 2; 4]);
+        assert_eq!(Store::len(), 2);
+
+        Store::clear();
+        assert!(Store::is_empty());
+    }
+
+    assert_eq!(Store::len(), 1);
+
+    // installing inside a scope only replaces the scoped store
+    Store::install(HostStore::default());
+    assert_eq!(Store::len(), 0);
+    drop(outer);
+}
@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::thread;
+
+use canonical::{Id, Repr, SharedStore, Store};
+
+const VALUE: [u64; 4] = [u64::MAX; 4];
+
+#[test]
+fn reify_across_threads() {
+    let store = SharedStore::default();
+
+    let ids: Vec<Id> = (0..4)
+        .map(|i| {
+            let store = store.clone();
+            thread::spawn(move || {
+                Store::install(store);
+                Id::new(&Repr::new([u64::MAX - i; 4]))
+            })
+            .join()
+            .unwrap()
+        })
+        .collect();
+
+    Store::install(store.clone());
+    for (i, id) in ids.iter().enumerate() {
+        let repr: Repr<[u64; 4]> = id.reify().unwrap();
+        assert_eq!(*repr.val().unwrap(), [u64::MAX - i as u64; 4]);
+    }
+}
+
+#[test]
+fn same_semantics_as_host_store() {
+    let store = SharedStore::default();
+    Store::install(store.clone());
+
+    let id = Id::new(&VALUE);
+    Id::new(&VALUE);
+    assert_eq!(store.refs(&id.hash()), 2);
+
+    let other = thread::spawn(move || {
+        Store::install(SharedStore::default());
+        Store::contains(&id.hash())
+    });
+    assert!(!other.join().unwrap());
+
+    assert!(id.take_bytes().unwrap().is_some());
+    assert_eq!(Store::collect_garbage(&[]).entries, 1);
+    assert!(!Store::contains(&id.hash()));
+}
+
+#[test]
+fn global_store() {
+    Store::install(SharedStore::global());
+    let id = Id::new(&VALUE);
+
+    let restored = thread::spawn(move || {
+        Store::install(SharedStore::global());
+        id.reify::<[u64; 4]>().unwrap()
+    });
+    assert_eq!(restored.join().unwrap(), VALUE);
+}
+
+#[cfg(feature = "shared-store")]
+#[test]
+fn shared_by_default() {
+    let id = Id::new(&[u64::MAX - 1; 4]);
+
+    let restored = thread::spawn(move || id.reify::<[u64; 4]>().unwrap());
+    assert_eq!(restored.join().unwrap(), [u64::MAX - 1; 4]);
+}
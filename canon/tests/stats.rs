@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use canonical::{
+    CanonError, Id, IdHash, Store, StoreObserver, StoreScope, StoreStats,
+};
+
+const VALUE: [u64; 4] = [u64::MAX; 4];
+
+#[test]
+fn counters() {
+    let _scope = StoreScope::new();
+    Store::reset_stats();
+
+    let id = Id::new(&VALUE);
+    Id::new(&VALUE);
+    // inlined values never reach the store
+    Id::new(&1u8);
+
+    id.reify::<[u64; 4]>().unwrap();
+    assert!(Store::get(&[0u8; 32], &mut [0u8; 40]).is_err());
+    id.take_bytes().unwrap();
+
+    assert_eq!(
+        Store::stats(),
+        StoreStats {
+            puts: 2,
+            duplicate_puts: 1,
+            gets: 2,
+            misses: 1,
+            takes: 1,
+            bytes_stored: id.size() as u64,
+            bytes_deduplicated: id.size() as u64,
+        }
+    );
+
+    Store::reset_stats();
+    assert_eq!(Store::stats(), StoreStats::default());
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Put(bool),
+    Get(bool),
+    Take(bool),
+}
+
+struct Recorder(Rc<RefCell<Vec<Event>>>);
+
+impl StoreObserver for Recorder {
+    fn on_put(&self, _: &IdHash, _: usize, duplicate: bool) {
+        self.0.borrow_mut().push(Event::Put(duplicate))
+    }
+
+    fn on_get(&self, _: &IdHash, result: &Result<(), CanonError>) {
+        self.0.borrow_mut().push(Event::Get(result.is_ok()))
+    }
+
+    fn on_take(&self, _: &Id, result: &Result<(), CanonError>) {
+        self.0.borrow_mut().push(Event::Take(result.is_ok()))
+    }
+}
+
+#[test]
+fn observers() {
+    let _scope = StoreScope::new();
+    let events = Rc::new(RefCell::new(vec![]));
+    Store::observe(Recorder(events.clone()));
+
+    let id = Id::new(&VALUE);
+    Id::new(&VALUE);
+    id.reify::<[u64; 4]>().unwrap();
+    id.take_bytes().unwrap();
+    id.take_bytes().unwrap();
+    assert!(id.take_bytes().is_err());
+    assert!(id.reify::<[u64; 4]>().is_err());
+
+    Store::clear_observers();
+    Id::new(&VALUE);
+
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            Event::Put(false),
+            Event::Put(true),
+            Event::Get(true),
+            Event::Take(true),
+            Event::Take(true),
+            Event::Take(false),
+            Event::Get(false),
+        ]
+    );
+}
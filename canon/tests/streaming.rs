@@ -0,0 +1,122 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::io;
+
+use canonical::{
+    Canon, CanonError, DecodeFromReader, EncodeToVec, EncodeToWriter, Repr,
+    Source,
+};
+use canonical_derive::Canon;
+
+mod common;
+use common::Tree;
+
+#[derive(Clone, Canon, Debug, PartialEq)]
+struct Record {
+    name: String,
+    values: Vec<u64>,
+    blob: Vec<u8>,
+}
+
+fn record() -> Record {
+    Record {
+        name: "streamed".into(),
+        values: (0..1000).map(|n| n * 997).collect(),
+        blob: (0..20_000).map(|n| n as u8).collect(),
+    }
+}
+
+// Records the size of every write it receives
+#[derive(Default)]
+struct Recorder {
+    bytes: Vec<u8>,
+    largest_write: usize,
+}
+
+impl io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.largest_write = self.largest_write.max(buf.len());
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Failing;
+
+impl io::Write for Failing {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn same_bytes_as_vec() {
+    let record = record();
+    let mut recorder = Recorder::default();
+    record.encode_to_writer(&mut recorder).unwrap();
+
+    assert_eq!(recorder.bytes, record.encode_to_vec());
+    assert!(recorder.largest_write <= 4096);
+
+    let tree = Tree::Node(
+        Repr::new(Tree::Leaf([u64::MAX; 4])),
+        Repr::new(Tree::Leaf([1; 4])),
+    );
+    let mut bytes = vec![];
+    tree.encode_to_writer(&mut bytes).unwrap();
+    assert_eq!(bytes, tree.encode_to_vec());
+}
+
+#[test]
+fn round_trip() {
+    let mut bytes = vec![];
+    record().encode_to_writer(&mut bytes).unwrap();
+    7u32.encode_to_writer(&mut bytes).unwrap();
+
+    // only the bytes of each value are consumed
+    let mut reader = &bytes[..];
+    assert_eq!(Record::decode_from_reader(&mut reader).unwrap(), record());
+    assert_eq!(u32::decode_from_reader(&mut reader).unwrap(), 7);
+    assert!(reader.is_empty());
+}
+
+#[test]
+fn truncated_input() {
+    let bytes = record().encode_to_vec();
+    let err =
+        Record::decode_from_reader(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn invalid_input() {
+    let err = Option::<u8>::decode_from_reader(&[7u8][..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn write_error() {
+    let err = record().encode_to_writer(Failing).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn overlong_varint() {
+    let bytes = [0xff; 11];
+    assert!(matches!(
+        u64::decode(&mut Source::new(&bytes)),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
@@ -0,0 +1,116 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::io;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+use canonical::{
+    Canon, ChannelTransport, EncodeToVec, HostStore, Id, Message, Source,
+    Store, StoreScope, Transport,
+};
+
+mod common;
+use common::{leaf, leaves, node, Tree};
+
+fn tree() -> Tree {
+    node(node(leaf(0), leaf(1)), node(leaf(2), leaf(3)))
+}
+
+// Serves the built tree from a store on another thread
+fn serve(
+    build: fn() -> Tree,
+) -> (Id, ChannelTransport, JoinHandle<io::Result<usize>>) {
+    let (mut sender, receiver) = ChannelTransport::pair();
+    let (root_tx, root_rx) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        Store::install(HostStore::default());
+        root_tx.send(Id::new(&build())).unwrap();
+        Store::send_tree(&mut sender)
+    });
+
+    (root_rx.recv().unwrap(), receiver, handle)
+}
+
+#[test]
+fn fetches_whole_tree() {
+    Store::install(HostStore::default());
+    let (root, mut transport, handle) = serve(tree);
+
+    let received = Store::fetch_tree(&root, &mut transport).unwrap();
+
+    // the root, two inner nodes and four leaves
+    assert_eq!(received, 7);
+    assert_eq!(handle.join().unwrap().unwrap(), 7);
+
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn skips_values_present() {
+    Store::install(HostStore::default());
+    Id::new(&node(leaf(2), leaf(3)));
+
+    let (root, mut transport, handle) = serve(tree);
+
+    let received = Store::fetch_tree(&root, &mut transport).unwrap();
+
+    // the subtree already present is not descended into
+    assert_eq!(received, 4);
+    assert_eq!(handle.join().unwrap().unwrap(), 4);
+
+    let tree: Tree = root.reify().unwrap();
+    assert_eq!(leaves(&tree).unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn missing_root() {
+    Store::install(HostStore::default());
+    let (_, mut transport, handle) = serve(tree);
+
+    let unknown = {
+        let _scope = StoreScope::new();
+        Id::new(&node(leaf(7), leaf(8)))
+    };
+    let err = Store::fetch_tree(&unknown, &mut transport).unwrap_err();
+
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(
+        handle.join().unwrap().unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn message_encoding() {
+    let messages = [
+        Message::Want(Id::new(&tree())),
+        Message::Offer(vec![[1; 32], [2; 32]]),
+        Message::Need(vec![]),
+        Message::Blob(vec![3; 40]),
+        Message::Missing([4; 32]),
+        Message::Done,
+    ];
+
+    for message in messages {
+        let bytes = message.encode_to_vec();
+        let decoded = Message::decode(&mut Source::new(&bytes)).unwrap();
+        assert_eq!(decoded, message);
+    }
+}
+
+#[test]
+fn closed_transport() {
+    let (mut a, b) = ChannelTransport::pair();
+    drop(b);
+
+    assert_eq!(
+        a.receive().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
@@ -0,0 +1,114 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    Canon, CanonError, EncodeToVec, Id, Proof, Repr, Sink, Source, Store,
+    StoreScope,
+};
+use canonical_derive::Canon;
+
+#[derive(Clone, Canon, Debug, PartialEq)]
+#[canon(tag = "leaf")]
+struct Leaf([u64; 4]);
+
+#[derive(Clone, Canon, Debug, PartialEq)]
+#[canon(tag = "node")]
+struct Node([u64; 4]);
+
+#[derive(Clone, Canon, Debug, PartialEq)]
+struct Plain([u64; 4]);
+
+#[derive(Clone, Canon, Debug)]
+struct Pair(Repr<Leaf>, Repr<Leaf>);
+
+// A tag set by hand rather than derived
+#[derive(Clone, Debug, PartialEq)]
+struct Small(u8);
+
+impl Canon for Small {
+    const TAG: Option<&'static str> = Some("small");
+
+    fn encode(&self, sink: &mut Sink) {
+        self.0.encode(sink)
+    }
+
+    fn decode(source: &mut Source) -> Result<Self, CanonError> {
+        Ok(Small(u8::decode(source)?))
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+const VALUES: [u64; 4] = [u64::MAX; 4];
+
+#[test]
+fn tags_separate_ids() {
+    let _scope = StoreScope::new();
+
+    let leaf = Id::new(&Leaf(VALUES));
+    let node = Id::new(&Node(VALUES));
+    let plain = Id::new(&Plain(VALUES));
+
+    assert_eq!(Leaf(VALUES).encode_to_vec(), Node(VALUES).encode_to_vec());
+    assert_ne!(leaf, node);
+    assert_ne!(leaf, plain);
+    assert_ne!(node, plain);
+
+    // untagged types hash their bare encoding
+    assert_eq!(plain.hash(), Store::hash(&Plain(VALUES).encode_to_vec()));
+    assert_eq!(Store::len(), 3);
+}
+
+#[test]
+fn tagged_inlined() {
+    let small = Id::new(&Small(7));
+
+    assert_ne!(small, Id::new(&7u8));
+    assert_ne!(small.hash(), Id::new(&7u8).hash());
+    assert_eq!(small.size(), 1 + "small".len() + 1);
+    assert_eq!(small.reify::<Small>().unwrap(), Small(7));
+}
+
+#[test]
+fn reify_checks_tag() {
+    let _scope = StoreScope::new();
+    Store::set_verify(true);
+
+    let id = Id::new(&Leaf(VALUES));
+    assert_eq!(id.reify::<Leaf>().unwrap(), Leaf(VALUES));
+
+    assert!(matches!(
+        id.reify::<Node>(),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn tagged_repr() {
+    let _scope = StoreScope::new();
+
+    let pair = Pair(Repr::new(Leaf(VALUES)), Repr::new(Leaf([1; 4])));
+    let bytes = pair.encode_to_vec();
+    assert_eq!(bytes.len(), pair.encoded_len());
+
+    let pair = Pair::decode(&mut Source::new(&bytes)).unwrap();
+    assert_eq!(*pair.0.val().unwrap(), Leaf(VALUES));
+    assert_eq!(*pair.1.val().unwrap(), Leaf([1; 4]));
+}
+
+#[test]
+fn proves_tagged_value() {
+    let _scope = StoreScope::new();
+
+    let root = Id::new(&Pair(Repr::new(Leaf(VALUES)), Repr::new(Leaf([1; 4]))));
+    let proof = Proof::new::<Pair>(&root, &[0]).unwrap();
+
+    assert!(proof.verify::<Pair, _>(&root, &Leaf(VALUES)));
+    assert!(!proof.verify::<Pair, _>(&root, &Node(VALUES)));
+    assert!(!proof.verify::<Pair, _>(&root, &Plain(VALUES)));
+}
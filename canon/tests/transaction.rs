@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{CanonError, Id, Store, StoreScope, Transaction};
+
+const A: [u64; 4] = [u64::MAX; 4];
+const B: [u64; 4] = [u64::MAX - 1; 4];
+
+#[test]
+fn commit() {
+    let _scope = StoreScope::new();
+    let a = Id::new(&A);
+
+    let transaction = Transaction::begin();
+    let b = Id::new(&B);
+    // reads fall through to the underlying store
+    assert_eq!(a.reify::<[u64; 4]>().unwrap(), A);
+    assert_eq!(Store::len(), 2);
+    transaction.commit();
+
+    assert_eq!(b.reify::<[u64; 4]>().unwrap(), B);
+    assert_eq!(Store::len(), 2);
+}
+
+#[test]
+fn rollback() {
+    let _scope = StoreScope::new();
+    let a = Id::new(&A);
+
+    let transaction = Transaction::begin();
+    let b = Id::new(&B);
+    assert!(a.take_bytes().unwrap().is_some());
+    assert!(!Store::contains(&a.hash()));
+    transaction.rollback();
+
+    assert_eq!(a.reify::<[u64; 4]>().unwrap(), A);
+    assert!(matches!(b.reify::<[u64; 4]>(), Err(CanonError::NotFound)));
+}
+
+#[test]
+fn takes_applied_on_commit() {
+    let _scope = StoreScope::new();
+    let a = Id::new(&A);
+
+    let transaction = Transaction::begin();
+    assert!(a.take_bytes().unwrap().is_some());
+    assert!(Store::is_empty());
+    transaction.commit();
+
+    assert!(matches!(a.reify::<[u64; 4]>(), Err(CanonError::NotFound)));
+}
+
+#[test]
+fn closure() {
+    let _scope = StoreScope::new();
+
+    let failed: Result<Id, ()> = Store::transaction(|| {
+        Id::new(&A);
+        Err(())
+    });
+    assert!(failed.is_err());
+    assert!(Store::is_empty());
+
+    let b = Store::transaction(|| Ok::<_, ()>(Id::new(&B))).unwrap();
+    assert_eq!(b.reify::<[u64; 4]>().unwrap(), B);
+}
+
+#[test]
+fn nested() {
+    let _scope = StoreScope::new();
+
+    let outer = Transaction::begin();
+    let a = Id::new(&A);
+
+    let inner = Transaction::begin();
+    let b = Id::new(&B);
+    inner.commit();
+
+    assert!(Store::contains(&b.hash()));
+    drop(outer);
+
+    assert!(!Store::contains(&a.hash()));
+    assert!(!Store::contains(&b.hash()));
+}
+
+#[test]
+fn release_counts_base_refs() {
+    let _scope = StoreScope::new();
+    let a = Id::new(&A);
+    Id::new(&A);
+
+    let transaction = Transaction::begin();
+    a.release().unwrap();
+    // the base still holds another reference
+    assert!(Store::contains(&a.hash()));
+    assert_eq!(a.reify::<[u64; 4]>().unwrap(), A);
+    assert_eq!(Store::len(), 1);
+
+    a.release().unwrap();
+    assert!(!Store::contains(&a.hash()));
+    assert_eq!(Store::len(), 0);
+    assert!(matches!(a.release(), Err(CanonError::NotFound)));
+    transaction.rollback();
+
+    let transaction = Transaction::begin();
+    a.release().unwrap();
+    transaction.commit();
+    assert_eq!(a.reify::<[u64; 4]>().unwrap(), A);
+}
+
+#[test]
+fn no_garbage_collected() {
+    let _scope = StoreScope::new();
+    let a = Id::new(&A);
+
+    let transaction = Transaction::begin();
+    Id::new(&B);
+    assert_eq!(Store::collect_garbage(&[]), Default::default());
+    transaction.commit();
+
+    assert!(Store::contains(&a.hash()));
+    assert_eq!(Store::collect_garbage(&[]).entries, 2);
+}
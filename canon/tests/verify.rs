@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    CanonError, HostStore, Id, IdHash, Store, StoreBackend, StoreScope,
+};
+
+// Flips a bit of every value it returns
+#[derive(Default)]
+struct Corrupting(HostStore);
+
+impl StoreBackend for Corrupting {
+    fn put(&self, bytes: &[u8]) -> IdHash {
+        self.0.put(bytes)
+    }
+
+    fn get(&self, hash: &IdHash, into: &mut [u8]) -> Result<(), CanonError> {
+        self.0.get(hash, into)?;
+        into[into.len() - 1] ^= 1;
+        Ok(())
+    }
+
+    fn take_bytes(&self, id: &Id) -> Result<Vec<u8>, CanonError> {
+        self.0.take_bytes(id)
+    }
+
+    fn contains(&self, hash: &IdHash) -> bool {
+        self.0.contains(hash)
+    }
+
+    fn size_of(&self, hash: &IdHash) -> Option<usize> {
+        self.0.size_of(hash)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn clear(&self) {
+        self.0.clear()
+    }
+
+    fn get_ref(&self, name: &str) -> Option<Id> {
+        self.0.get_ref(name)
+    }
+
+    fn set_ref(&self, name: &str, id: Option<Id>) -> Option<Id> {
+        self.0.set_ref(name, id)
+    }
+
+    fn compare_and_swap_ref(
+        &self,
+        name: &str,
+        current: Option<Id>,
+        new: Option<Id>,
+    ) -> Result<(), Option<Id>> {
+        self.0.compare_and_swap_ref(name, current, new)
+    }
+
+    fn list_refs(&self) -> Vec<(String, Id)> {
+        self.0.list_refs()
+    }
+}
+
+#[test]
+fn detects_corruption() {
+    let _scope = StoreScope::with_backend(Corrupting::default());
+    let id = Id::new(&[u64::MAX; 4]);
+
+    assert!(!Store::verifying());
+    // without verification the corruption goes unnoticed by the store
+    assert_ne!(id.reify::<[u64; 4]>().ok(), Some([u64::MAX; 4]));
+
+    Store::set_verify(true);
+    assert!(matches!(
+        id.reify::<[u64; 4]>(),
+        Err(CanonError::HashMismatch)
+    ));
+    Store::set_verify(false);
+}
+
+#[test]
+fn passes_intact_values() {
+    let _scope = StoreScope::new();
+    Store::set_verify(true);
+
+    let id = Id::new(&[u64::MAX; 4]);
+    assert_eq!(id.reify::<[u64; 4]>().unwrap(), [u64::MAX; 4]);
+    assert!(matches!(
+        Id::new(&[1u8; 4]).reify::<[u8; 4]>(),
+        Ok([1, 1, 1, 1])
+    ));
+}
@@ -8,12 +8,12 @@
 
 #![deny(missing_docs)]
 
-use proc_macro2::{Ident, Literal};
+use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{quote, quote_spanned};
 use syn::spanned::Spanned;
 use syn::{
-    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam,
-    Generics,
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields,
+    GenericParam, Generics, Lit, Meta, NestedMeta,
 };
 
 const FIELD_NAMES: [&str; 16] = [
@@ -30,12 +30,60 @@ fn add_trait_bounds(mut generics: Generics) -> Generics {
     generics
 }
 
-#[proc_macro_derive(Canon)]
+// Reads the domain tag from a `#[canon(tag = "...")]` attribute
+fn tag(attrs: &[Attribute]) -> syn::Result<TokenStream> {
+    let mut tag = quote! {};
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("canon")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            other => {
+                return Err(syn::Error::new(other.span(), "expected canon(..)"))
+            }
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv))
+                    if nv.path.is_ident("tag") =>
+                {
+                    let lit = match nv.lit {
+                        Lit::Str(lit) => lit,
+                        other => {
+                            return Err(syn::Error::new(
+                                other.span(),
+                                "expected a string tag",
+                            ))
+                        }
+                    };
+                    tag = quote! {
+                        const TAG: Option<&'static str> = Some(#lit);
+                    };
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        other.span(),
+                        "unknown canon attribute",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(tag)
+}
+
+#[proc_macro_derive(Canon, attributes(canon))]
 /// Derive macro that implements the serialization method for a type
+///
+/// A domain tag for the `Id`s of the type can be set with
+/// `#[canon(tag = "...")]`.
 pub fn canon_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident.clone();
 
+    let tag = match tag(&input.attrs) {
+        Ok(tag) => tag,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let generics = add_trait_bounds(input.generics.clone());
 
     let (_, ty_generics, where_clause) = generics.split_for_impl();
@@ -229,6 +277,8 @@ pub fn canon_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
     let output = quote! {
         impl #generics canonical::Canon for #name #ty_generics #where_clause {
+            #tag
+
             fn encode(&self, sink: &mut canonical::Sink) {
                 #encode
                 ;
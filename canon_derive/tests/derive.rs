@@ -5,7 +5,7 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use arbitrary::Arbitrary;
-use canonical::{Canon, Id};
+use canonical::{Canon, CanonError, EncodeToVec, Id, Source};
 use canonical_derive::Canon;
 use canonical_fuzz::fuzz_canon_iterations;
 
@@ -59,6 +59,10 @@ struct I<T>(Vec<T>);
 #[derive(Clone, Canon, PartialEq, Debug, Arbitrary)]
 struct J(String);
 
+#[derive(Clone, Canon, PartialEq, Debug, Arbitrary)]
+#[canon(tag = "k")]
+struct K(u64);
+
 #[derive(Clone, Canon, PartialEq, Debug, Arbitrary)]
 struct MonsterStruct<T> {
     a: A,
@@ -98,6 +102,7 @@ fn derives() {
     serialize_deserialize(H(73u64));
     serialize_deserialize(H(E::B));
     serialize_deserialize(H(F::B(83)));
+    serialize_deserialize(K(73));
 
     serialize_deserialize(MonsterStruct {
         a: A { a: 37, b: 77 },
@@ -112,6 +117,26 @@ fn derives() {
     });
 }
 
+#[test]
+fn tags() {
+    assert_eq!(A::TAG, None);
+    assert_eq!(K::TAG, Some("k"));
+    assert_ne!(Id::new(&K(73)), Id::new(&C(73)));
+}
+
+#[test]
+fn truncated() {
+    let value = G::A { alice: 73, bob: 3 };
+    let bytes = value.encode_to_vec();
+
+    for len in 0..bytes.len() {
+        assert!(matches!(
+            G::decode(&mut Source::new(&bytes[..len])),
+            Err(CanonError::UnexpectedEof)
+        ));
+    }
+}
+
 #[test]
 fn fuzzing() {
     fuzz_canon_iterations::<MonsterStruct<Option<u32>>>(32);
@@ -0,0 +1,391 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! # Canonical host
+//!
+//! Host side of the `canon` wasm import module called by `BridgeStore`.
+//!
+//! `CanonHost` implements every import against the linear memory of the
+//! calling module, abstracted by the `Memory` trait, and any
+//! `StoreBackend`. Pointers and lengths are taken as the module passes
+//! them. The `wasmi` feature adds `link`, defining the imports in a wasmi
+//! `Linker`.
+
+#![deny(missing_docs)]
+
+use core::convert::TryFrom;
+
+use canonical::{
+    BridgeStatus, CanonError, HashAlgorithm, IdHash, StoreBackend,
+    BRIDGE_ABI_VERSION,
+};
+
+#[cfg(feature = "wasmi")]
+mod linker;
+#[cfg(feature = "wasmi")]
+pub use linker::link;
+
+const HASH_BYTES: usize = core::mem::size_of::<IdHash>();
+
+/// The linear memory of a wasm module
+///
+/// Accesses out of bounds fail with `CanonError::InvalidEncoding`. Lengths
+/// passed by the module are checked against `size` before any buffer is
+/// allocated for them.
+pub trait Memory {
+    /// Returns the size of the memory in bytes
+    fn size(&self) -> usize;
+
+    /// Copies the bytes at `ptr` into `buf`
+    fn read(&self, ptr: u32, buf: &mut [u8]) -> Result<(), CanonError>;
+
+    /// Copies `bytes` into the memory at `ptr`
+    fn write(&mut self, ptr: u32, bytes: &[u8]) -> Result<(), CanonError>;
+}
+
+impl Memory for [u8] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read(&self, ptr: u32, buf: &mut [u8]) -> Result<(), CanonError> {
+        let start = ptr as usize;
+        let bytes = start
+            .checked_add(buf.len())
+            .and_then(|end| self.get(start..end))
+            .ok_or(CanonError::InvalidEncoding)?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write(&mut self, ptr: u32, bytes: &[u8]) -> Result<(), CanonError> {
+        let start = ptr as usize;
+        let into = start
+            .checked_add(bytes.len())
+            .and_then(|end| self.get_mut(start..end))
+            .ok_or(CanonError::InvalidEncoding)?;
+        into.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Memory for Vec<u8> {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read(&self, ptr: u32, buf: &mut [u8]) -> Result<(), CanonError> {
+        self[..].read(ptr, buf)
+    }
+
+    fn write(&mut self, ptr: u32, bytes: &[u8]) -> Result<(), CanonError> {
+        self[..].write(ptr, bytes)
+    }
+}
+
+// Converts a length passed by the module
+fn length(len: i32) -> Result<usize, CanonError> {
+    if len < 0 {
+        Err(CanonError::InvalidEncoding)
+    } else {
+        Ok(len as usize)
+    }
+}
+
+// Checks that `len` bytes at `ptr` lie within the memory, before anything
+// is allocated for them
+fn check<M: Memory + ?Sized>(
+    memory: &M,
+    ptr: i32,
+    len: usize,
+) -> Result<(), CanonError> {
+    match (ptr as u32 as usize).checked_add(len) {
+        Some(end) if end <= memory.size() => Ok(()),
+        _ => Err(CanonError::InvalidEncoding),
+    }
+}
+
+// Multiplies a count passed by the module by the size of its elements
+fn times(count: usize, size: usize) -> Result<usize, CanonError> {
+    count.checked_mul(size).ok_or(CanonError::InvalidEncoding)
+}
+
+// Sums lengths read from the module
+fn total(lens: &[usize]) -> Result<usize, CanonError> {
+    lens.iter().try_fold(0usize, |sum, len| {
+        sum.checked_add(*len).ok_or(CanonError::InvalidEncoding)
+    })
+}
+
+fn read_vec<M: Memory + ?Sized>(
+    memory: &M,
+    ptr: i32,
+    len: usize,
+) -> Result<Vec<u8>, CanonError> {
+    check(memory, ptr, len)?;
+    let mut buf = vec![0u8; len];
+    memory.read(ptr as u32, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_hash<M: Memory + ?Sized>(
+    memory: &M,
+    ptr: i32,
+) -> Result<IdHash, CanonError> {
+    let mut hash = IdHash::default();
+    memory.read(ptr as u32, &mut hash)?;
+    Ok(hash)
+}
+
+fn read_lens<M: Memory + ?Sized>(
+    memory: &M,
+    ptr: i32,
+    count: usize,
+) -> Result<Vec<usize>, CanonError> {
+    let bytes = read_vec(memory, ptr, times(count, 4)?)?;
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut len = [0u8; 4];
+            len.copy_from_slice(chunk);
+            length(i32::from_le_bytes(len))
+        })
+        .collect()
+}
+
+fn algorithm(version: i32) -> Result<HashAlgorithm, CanonError> {
+    u8::try_from(version)
+        .ok()
+        .and_then(HashAlgorithm::from_version)
+        .ok_or(CanonError::InvalidEncoding)
+}
+
+/// Serves the `canon` imports from a store backend
+///
+/// Every method is named after the import it implements. Imports returning
+/// a status code have it computed from the returned result with
+/// `BridgeStatus::from_result`. For the others, an error means the module
+/// passed invalid pointers and should be trapped.
+#[derive(Debug, Default)]
+pub struct CanonHost<B> {
+    backend: B,
+}
+
+impl<B> AsRef<CanonHost<B>> for CanonHost<B> {
+    fn as_ref(&self) -> &CanonHost<B> {
+        self
+    }
+}
+
+impl<B: StoreBackend> CanonHost<B> {
+    /// Creates a host serving values from `backend`
+    pub fn new(backend: B) -> Self {
+        CanonHost { backend }
+    }
+
+    /// Returns the backend of the host
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns the version of the import module implemented
+    pub fn abi_version(&self) -> i32 {
+        BRIDGE_ABI_VERSION as i32
+    }
+
+    /// Stores `len` bytes at `buf`, writing their Blake2b hash to `ret_hash`
+    pub fn put<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        buf: i32,
+        len: i32,
+        ret_hash: i32,
+    ) -> Result<(), CanonError> {
+        let bytes = read_vec(memory, buf, length(len)?)?;
+        let hash = self.backend.put(&bytes);
+        memory.write(ret_hash as u32, &hash)
+    }
+
+    /// Stores `count` values concatenated at `bufs`, their lengths being
+    /// little endian `i32`s at `lens`
+    ///
+    /// The hashes are written to `ret_hashes` and a byte per value to
+    /// `ret_new`, set if the backend did not hold the value before.
+    #[allow(clippy::too_many_arguments)]
+    pub fn put_many<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        algorithm_version: i32,
+        bufs: i32,
+        lens: i32,
+        count: i32,
+        ret_hashes: i32,
+        ret_new: i32,
+    ) -> Result<(), CanonError> {
+        let algorithm = algorithm(algorithm_version)?;
+        let count = length(count)?;
+        let lens = read_lens(memory, lens, count)?;
+        let bytes = read_vec(memory, bufs, total(&lens)?)?;
+        check(memory, ret_hashes, times(count, HASH_BYTES)?)?;
+        check(memory, ret_new, count)?;
+
+        let mut values = Vec::with_capacity(lens.len());
+        let mut offset = 0;
+        for len in &lens {
+            values.push(&bytes[offset..offset + len]);
+            offset += len;
+        }
+
+        let inserted = self.backend.insert_many(algorithm, &values)?;
+        let hashes: Vec<u8> = inserted
+            .iter()
+            .flat_map(|(hash, _)| hash.to_vec())
+            .collect();
+        let new: Vec<u8> = inserted.iter().map(|(_, new)| *new as u8).collect();
+
+        memory.write(ret_hashes as u32, &hashes)?;
+        memory.write(ret_new as u32, &new)
+    }
+
+    /// Writes the `len` bytes stored under the hash at `hash` to `buf`
+    pub fn get<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hash: i32,
+        buf: i32,
+        len: i32,
+    ) -> Result<(), CanonError> {
+        let hash = read_hash(memory, hash)?;
+        let len = length(len)?;
+        check(memory, buf, len)?;
+        let mut bytes = vec![0u8; len];
+        self.backend.get(&hash, &mut bytes)?;
+        memory.write(buf as u32, &bytes)
+    }
+
+    /// Writes the values stored under `count` hashes at `hashes` to `buf`,
+    /// concatenated, with their lengths read from `lens`
+    ///
+    /// The status of each value is written to `ret_status` as a little
+    /// endian `i32`.
+    pub fn get_many<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hashes: i32,
+        lens: i32,
+        count: i32,
+        buf: i32,
+        ret_status: i32,
+    ) -> Result<(), CanonError> {
+        let count = length(count)?;
+        let hash_bytes = read_vec(memory, hashes, times(count, HASH_BYTES)?)?;
+        let lens = read_lens(memory, lens, count)?;
+        let len = total(&lens)?;
+        check(memory, buf, len)?;
+        check(memory, ret_status, times(count, 4)?)?;
+
+        let hashes: Vec<IdHash> = hash_bytes
+            .chunks(HASH_BYTES)
+            .map(|chunk| {
+                let mut hash = IdHash::default();
+                hash.copy_from_slice(chunk);
+                hash
+            })
+            .collect();
+
+        let mut bytes = vec![0u8; len];
+        let mut into = Vec::with_capacity(count);
+        let mut rest = &mut bytes[..];
+        for len in &lens {
+            let (head, tail) = rest.split_at_mut(*len);
+            into.push(head);
+            rest = tail;
+        }
+
+        let results = self.backend.get_many(&hashes, &mut into);
+        let statuses: Vec<u8> = results
+            .iter()
+            .flat_map(|result| {
+                BridgeStatus::from_result(result).code().to_le_bytes()
+            })
+            .collect();
+
+        memory.write(buf as u32, &bytes)?;
+        memory.write(ret_status as u32, &statuses)
+    }
+
+    /// Writes the `len` bytes stored under the hash at `hash` to `buf`,
+    /// dropping a reference to them
+    pub fn take<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hash: i32,
+        buf: i32,
+        len: i32,
+    ) -> Result<(), CanonError> {
+        let hash = read_hash(memory, hash)?;
+        let len = length(len)?;
+        check(memory, buf, len)?;
+        let mut bytes = vec![0u8; len];
+        self.backend.get(&hash, &mut bytes)?;
+        memory.write(buf as u32, &bytes)?;
+        self.backend.release(&hash)
+    }
+
+    /// Drops a reference to the value stored under the hash at `hash`
+    pub fn remove<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hash: i32,
+    ) -> Result<(), CanonError> {
+        self.backend.release(&read_hash(memory, hash)?)
+    }
+
+    /// Returns the number of values in the backend
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> i64 {
+        self.backend.len() as i64
+    }
+
+    /// Removes every value and every ref from the backend
+    pub fn clear(&self) {
+        self.backend.clear()
+    }
+
+    /// Returns the number of references held to the value stored under the
+    /// hash at `hash`, 0 if the backend does not hold it
+    pub fn refs<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hash: i32,
+    ) -> Result<i64, CanonError> {
+        let refs = self.backend.refs(&read_hash(memory, hash)?);
+        Ok(i64::try_from(refs).unwrap_or(i64::MAX))
+    }
+
+    /// Writes the Blake2b hash of the `len` bytes at `buf` to `ret_hash`
+    pub fn hash<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        buf: i32,
+        len: i32,
+        ret_hash: i32,
+    ) -> Result<(), CanonError> {
+        let bytes = read_vec(memory, buf, length(len)?)?;
+        memory.write(ret_hash as u32, &self.backend.hash(&bytes))
+    }
+
+    /// Returns the length of the value stored under the hash at `hash`, or
+    /// -1 if the backend does not hold it
+    pub fn size<M: Memory + ?Sized>(
+        &self,
+        memory: &mut M,
+        hash: i32,
+    ) -> Result<i64, CanonError> {
+        let hash = read_hash(memory, hash)?;
+        Ok(self.backend.size_of(&hash).map_or(-1, |len| len as i64))
+    }
+}
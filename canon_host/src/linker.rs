@@ -0,0 +1,169 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{BridgeStatus, CanonError, StoreBackend};
+use wasmi::core::Trap;
+use wasmi::errors::LinkerError;
+use wasmi::{Caller, Extern, Linker};
+
+use crate::CanonHost;
+
+const MODULE: &str = "canon";
+
+// Runs `f` with the host in the store data and the memory exported by the
+// calling module
+fn with_host<T, B, R>(
+    caller: &mut Caller<'_, T>,
+    f: impl FnOnce(&CanonHost<B>, &mut [u8]) -> R,
+) -> Result<R, Trap>
+where
+    T: AsRef<CanonHost<B>>,
+    B: StoreBackend,
+{
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("The module does not export its memory"))?;
+    let (memory, data) = memory.data_and_store_mut(caller);
+    Ok(f(data.as_ref(), memory))
+}
+
+fn status(result: Result<(), CanonError>) -> i32 {
+    BridgeStatus::from_result(&result).code()
+}
+
+fn trap<R>(result: Result<R, CanonError>) -> Result<R, Trap> {
+    result.map_err(|err| {
+        Trap::new(format!("Invalid arguments to a canon import: {:?}", err))
+    })
+}
+
+/// Defines the `canon` imports in `linker`, served by the `CanonHost` held
+/// in the store data
+///
+/// Modules using the imports have to export their memory as `memory`.
+pub fn link<T, B>(linker: &mut Linker<T>) -> Result<(), LinkerError>
+where
+    T: AsRef<CanonHost<B>> + 'static,
+    B: StoreBackend + 'static,
+{
+    linker.func_wrap(MODULE, "abi_version", |caller: Caller<'_, T>| {
+        caller.data().as_ref().abi_version()
+    })?;
+
+    linker.func_wrap(
+        MODULE,
+        "put",
+        |mut caller: Caller<'_, T>, buf: i32, len: i32, ret_hash: i32| {
+            with_host(&mut caller, |host, memory| {
+                status(host.put(memory, buf, len, ret_hash))
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "put_many",
+        |mut caller: Caller<'_, T>,
+         algorithm: i32,
+         bufs: i32,
+         lens: i32,
+         count: i32,
+         ret_hashes: i32,
+         ret_new: i32| {
+            with_host(&mut caller, |host, memory| {
+                status(host.put_many(
+                    memory, algorithm, bufs, lens, count, ret_hashes, ret_new,
+                ))
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "get",
+        |mut caller: Caller<'_, T>, hash: i32, buf: i32, len: i32| {
+            with_host(&mut caller, |host, memory| {
+                status(host.get(memory, hash, buf, len))
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "get_many",
+        |mut caller: Caller<'_, T>,
+         hashes: i32,
+         lens: i32,
+         count: i32,
+         buf: i32,
+         ret_status: i32| {
+            trap(with_host(&mut caller, |host, memory| {
+                host.get_many(memory, hashes, lens, count, buf, ret_status)
+            })?)
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "take",
+        |mut caller: Caller<'_, T>, hash: i32, buf: i32, len: i32| {
+            with_host(&mut caller, |host, memory| {
+                status(host.take(memory, hash, buf, len))
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "remove",
+        |mut caller: Caller<'_, T>, hash: i32| {
+            with_host(&mut caller, |host, memory| {
+                status(host.remove(memory, hash))
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "refs",
+        |mut caller: Caller<'_, T>, hash: i32| {
+            trap(with_host(&mut caller, |host, memory| {
+                host.refs(memory, hash)
+            })?)
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "hash",
+        |mut caller: Caller<'_, T>, buf: i32, len: i32, ret_hash: i32| {
+            trap(with_host(&mut caller, |host, memory| {
+                host.hash(memory, buf, len, ret_hash)
+            })?)
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "size",
+        |mut caller: Caller<'_, T>, hash: i32| {
+            trap(with_host(&mut caller, |host, memory| {
+                host.size(memory, hash)
+            })?)
+        },
+    )?;
+
+    linker.func_wrap(MODULE, "len", |caller: Caller<'_, T>| {
+        caller.data().as_ref().len()
+    })?;
+
+    linker.func_wrap(MODULE, "clear", |caller: Caller<'_, T>| {
+        caller.data().as_ref().clear()
+    })?;
+
+    Ok(())
+}
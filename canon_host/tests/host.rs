@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use canonical::{
+    CanonError, HashAlgorithm, HostStore, IdHash, StoreBackend,
+    BRIDGE_ABI_VERSION,
+};
+use canonical_host::{CanonHost, Memory};
+
+const VALUE: i32 = 0;
+const HASH: i32 = 64;
+const OUT: i32 = 128;
+
+fn setup() -> (CanonHost<HostStore>, Vec<u8>) {
+    let mut memory = vec![0u8; 1024];
+    memory[..40].copy_from_slice(&[7; 40]);
+    (CanonHost::new(HostStore::default()), memory)
+}
+
+fn hash_at(memory: &[u8], ptr: i32) -> IdHash {
+    let mut hash = IdHash::default();
+    memory.read(ptr as u32, &mut hash).unwrap();
+    hash
+}
+
+#[test]
+fn put_and_get() {
+    let (host, mut memory) = setup();
+    assert_eq!(host.abi_version(), BRIDGE_ABI_VERSION as i32);
+
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+    let hash = hash_at(&memory, HASH);
+    assert_eq!(hash, HashAlgorithm::Blake2b.hash(&[7; 40]));
+    assert_eq!(host.size(&mut memory, HASH).unwrap(), 40);
+
+    host.get(&mut memory, HASH, OUT, 40).unwrap();
+    assert_eq!(&memory[128..168], &[7; 40]);
+}
+
+#[test]
+fn get_errors() {
+    let (host, mut memory) = setup();
+
+    assert!(matches!(
+        host.get(&mut memory, HASH, OUT, 40),
+        Err(CanonError::NotFound)
+    ));
+    assert_eq!(host.size(&mut memory, HASH).unwrap(), -1);
+
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+    assert!(matches!(
+        host.get(&mut memory, HASH, OUT, 41),
+        Err(CanonError::InvalidEncoding)
+    ));
+    assert!(matches!(
+        host.get(&mut memory, HASH, 1020, 40),
+        Err(CanonError::InvalidEncoding)
+    ));
+    assert!(matches!(
+        host.get(&mut memory, HASH, OUT, -1),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn take_and_remove() {
+    let (host, mut memory) = setup();
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+    let hash = hash_at(&memory, HASH);
+
+    assert_eq!(host.refs(&mut memory, HASH).unwrap(), 2);
+    host.take(&mut memory, HASH, OUT, 40).unwrap();
+    assert_eq!(&memory[128..168], &[7; 40]);
+    assert!(host.backend().contains(&hash));
+    assert_eq!(host.refs(&mut memory, HASH).unwrap(), 1);
+
+    host.remove(&mut memory, HASH).unwrap();
+    assert!(!host.backend().contains(&hash));
+    assert!(matches!(
+        host.take(&mut memory, HASH, OUT, 40),
+        Err(CanonError::NotFound)
+    ));
+}
+
+#[test]
+fn batches() {
+    let (host, mut memory) = setup();
+    memory[40..80].copy_from_slice(&[8; 40]);
+
+    // two values of 40 bytes, their lengths at 256
+    memory[256..260].copy_from_slice(&40i32.to_le_bytes());
+    memory[260..264].copy_from_slice(&40i32.to_le_bytes());
+
+    host.put_many(&mut memory, 1, VALUE, 256, 2, 512, 576)
+        .unwrap();
+    let a = hash_at(&memory, 512);
+    let b = hash_at(&memory, 544);
+    assert_eq!(a, HashAlgorithm::Sha256.hash(&[7; 40]));
+    assert_eq!(b, HashAlgorithm::Sha256.hash(&[8; 40]));
+    assert_eq!(&memory[576..578], &[1, 1]);
+
+    // a missing hash between the two
+    memory.copy_within(544..576, 576);
+    memory[544..576].copy_from_slice(&[0; 32]);
+    memory[264..268].copy_from_slice(&40i32.to_le_bytes());
+
+    host.get_many(&mut memory, 512, 256, 3, OUT, 640).unwrap();
+    assert_eq!(&memory[128..168], &[7; 40]);
+    assert_eq!(&memory[208..248], &[8; 40]);
+
+    let statuses: Vec<i32> = memory[640..652]
+        .chunks(4)
+        .map(|c| i32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+    assert_eq!(statuses, vec![0, 1, 0]);
+}
+
+#[test]
+fn len_and_clear() {
+    let (host, mut memory) = setup();
+    assert_eq!(host.len(), 0);
+
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+    assert_eq!(host.len(), 1);
+
+    host.clear();
+    assert_eq!(host.len(), 0);
+}
+
+#[test]
+fn unknown_algorithm() {
+    let (host, mut memory) = setup();
+    memory[256..260].copy_from_slice(&40i32.to_le_bytes());
+    assert!(matches!(
+        host.put_many(&mut memory, 9, VALUE, 256, 1, 512, 576),
+        Err(CanonError::InvalidEncoding)
+    ));
+}
+
+#[test]
+fn forged_lengths() {
+    let (host, mut memory) = setup();
+    host.put(&mut memory, VALUE, 40, HASH).unwrap();
+
+    let invalid = |result| matches!(result, Err(CanonError::InvalidEncoding));
+
+    // lengths and counts beyond the memory fail before allocating
+    assert!(invalid(host.put(&mut memory, VALUE, i32::MAX, HASH)));
+    assert!(invalid(host.hash(&mut memory, VALUE, i32::MAX, HASH)));
+    assert!(invalid(host.get(&mut memory, HASH, OUT, i32::MAX)));
+    assert!(invalid(host.take(&mut memory, HASH, OUT, i32::MAX)));
+    assert!(invalid(host.get(&mut memory, HASH, -1, 40)));
+    assert!(invalid(host.get_many(&mut memory, 0, 0, i32::MAX, 0, 0)));
+    assert!(invalid(host.put_many(&mut memory, 0, 0, 0, i32::MAX, 0, 0)));
+
+    // lengths summing past the memory
+    memory[256..260].copy_from_slice(&i32::MAX.to_le_bytes());
+    memory[260..264].copy_from_slice(&i32::MAX.to_le_bytes());
+    assert!(invalid(host.put_many(
+        &mut memory,
+        0,
+        VALUE,
+        256,
+        2,
+        512,
+        576
+    )));
+    let hash = hash_at(&memory, HASH);
+    memory[512..544].copy_from_slice(&hash);
+    memory[544..576].copy_from_slice(&hash);
+    assert!(invalid(host.get_many(&mut memory, 512, 256, 2, OUT, 640)));
+
+    // nothing is stored when the results cannot be written back
+    memory[256..260].copy_from_slice(&40i32.to_le_bytes());
+    memory[260..264].copy_from_slice(&40i32.to_le_bytes());
+    assert!(invalid(host.put_many(
+        &mut memory,
+        0,
+        VALUE,
+        256,
+        2,
+        1020,
+        576
+    )));
+    assert_eq!(host.backend().len(), 1);
+}
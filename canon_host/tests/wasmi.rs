@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "wasmi")]
+
+use canonical::{HashAlgorithm, HostStore, StoreBackend, BRIDGE_ABI_VERSION};
+use canonical_host::{link, CanonHost};
+use wasmi::{Engine, Instance, Linker, Module, Store};
+
+const MODULE: &str = r#"
+(module
+  (import "canon" "abi_version" (func $abi_version (result i32)))
+  (import "canon" "put" (func $put (param i32 i32 i32) (result i32)))
+  (import "canon" "get" (func $get (param i32 i32 i32) (result i32)))
+  (import "canon" "size" (func $size (param i32) (result i64)))
+  (import "canon" "hash" (func $hash (param i32 i32 i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "a value longer than an inlined payload")
+  (func (export "version") (result i32)
+    call $abi_version)
+  (func (export "put") (result i32)
+    (call $put (i32.const 0) (i32.const 38) (i32.const 64)))
+  (func (export "get") (result i32)
+    (call $get (i32.const 64) (i32.const 128) (i32.const 38)))
+  (func (export "size") (result i64)
+    (call $size (i32.const 64)))
+  (func (export "hash_out_of_bounds")
+    (call $hash (i32.const 65530) (i32.const 38) (i32.const 64)))
+)
+"#;
+
+const VALUE: &[u8] = b"a value longer than an inlined payload";
+
+fn instantiate() -> (Store<CanonHost<HostStore>>, Instance) {
+    let engine = Engine::default();
+    let wasm = wat::parse_str(MODULE).unwrap();
+    let module = Module::new(&engine, &wasm[..]).unwrap();
+
+    let mut store = Store::new(&engine, CanonHost::new(HostStore::default()));
+    let mut linker = Linker::new(&engine);
+    link(&mut linker).unwrap();
+
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    (store, instance)
+}
+
+fn call<R: wasmi::WasmResults>(
+    store: &mut Store<CanonHost<HostStore>>,
+    instance: &Instance,
+    name: &str,
+) -> Result<R, wasmi::core::Trap> {
+    instance
+        .get_typed_func::<(), R>(&*store, name)
+        .unwrap()
+        .call(store, ())
+}
+
+#[test]
+fn serves_module() {
+    let (mut store, instance) = instantiate();
+
+    let version: i32 = call(&mut store, &instance, "version").unwrap();
+    assert_eq!(version, BRIDGE_ABI_VERSION as i32);
+
+    // not stored yet
+    assert_eq!(call::<i32>(&mut store, &instance, "get").unwrap(), 1);
+    assert_eq!(call::<i64>(&mut store, &instance, "size").unwrap(), -1);
+
+    assert_eq!(call::<i32>(&mut store, &instance, "put").unwrap(), 0);
+    let hash = HashAlgorithm::Blake2b.hash(VALUE);
+    assert!(store.data().backend().contains(&hash));
+
+    assert_eq!(call::<i32>(&mut store, &instance, "get").unwrap(), 0);
+    assert_eq!(call::<i64>(&mut store, &instance, "size").unwrap(), 38);
+
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let data = memory.data(&store);
+    assert_eq!(&data[64..96], &hash);
+    assert_eq!(&data[128..166], VALUE);
+}
+
+#[test]
+fn traps_on_invalid_pointers() {
+    let (mut store, instance) = instantiate();
+    assert!(call::<()>(&mut store, &instance, "hash_out_of_bounds").is_err());
+}